@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
-use super::sea_orm_active_enums::Bodypart;
+use super::sea_orm_active_enums::{Equipment, TrackingType};
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -9,13 +9,29 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
     pub name: String,
-    pub bodypart: Bodypart,
+    pub bodypart: String,
     pub isfavorite: bool,
+    pub tracking_type: TrackingType,
+    pub equipment: Option<Equipment>,
+    pub progression_strategy: String,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub progression_settings: Option<Json>,
+    pub library_exercise_id: Option<i32>,
     pub user_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::exercise_muscles::Entity")]
+    ExerciseMuscles,
+    #[sea_orm(
+        belongs_to = "super::library_exercises::Entity",
+        from = "Column::LibraryExerciseId",
+        to = "super::library_exercises::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    LibraryExercises,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -26,6 +42,18 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::exercise_muscles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExerciseMuscles.def()
+    }
+}
+
+impl Related<super::library_exercises::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LibraryExercises.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -2,7 +2,24 @@
 
 pub mod prelude;
 
+pub mod cardio_sessions;
+pub mod categories;
+pub mod equipment_profiles;
+pub mod exercise_muscles;
 pub mod exercises;
+pub mod goals;
+pub mod library_exercises;
+pub mod measurements;
+pub mod plate_inventories;
+pub mod program_enrollments;
+pub mod programs;
+pub mod routine_exercises;
+pub mod routines;
 pub mod sea_orm_active_enums;
+pub mod stall_alerts;
+pub mod task_completions;
+pub mod tasks;
+pub mod user_achievements;
+pub mod user_profiles;
 pub mod users;
 pub mod workout_sets;
@@ -1,5 +1,22 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
+pub use super::cardio_sessions::Entity as CardioSessions;
+pub use super::categories::Entity as Categories;
+pub use super::equipment_profiles::Entity as EquipmentProfiles;
+pub use super::exercise_muscles::Entity as ExerciseMuscles;
 pub use super::exercises::Entity as Exercises;
+pub use super::goals::Entity as Goals;
+pub use super::library_exercises::Entity as LibraryExercises;
+pub use super::measurements::Entity as Measurements;
+pub use super::plate_inventories::Entity as PlateInventories;
+pub use super::program_enrollments::Entity as ProgramEnrollments;
+pub use super::programs::Entity as Programs;
+pub use super::routine_exercises::Entity as RoutineExercises;
+pub use super::routines::Entity as Routines;
+pub use super::stall_alerts::Entity as StallAlerts;
+pub use super::task_completions::Entity as TaskCompletions;
+pub use super::tasks::Entity as Tasks;
+pub use super::user_achievements::Entity as UserAchievements;
+pub use super::user_profiles::Entity as UserProfiles;
 pub use super::users::Entity as Users;
 pub use super::workout_sets::Entity as WorkoutSets;
@@ -3,21 +3,203 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
-#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "bodypart")]
-pub enum Bodypart {
-    #[sea_orm(string_value = "Abs")]
-    Abs,
-    #[sea_orm(string_value = "Back")]
-    Back,
-    #[sea_orm(string_value = "Biceps")]
-    Biceps,
-    #[sea_orm(string_value = "Chest")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "weight_unit")]
+pub enum WeightUnit {
+    #[sea_orm(string_value = "kg")]
+    Kg,
+    #[sea_orm(string_value = "lb")]
+    Lb,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "week_day")]
+pub enum WeekDay {
+    #[sea_orm(string_value = "Monday")]
+    Monday,
+    #[sea_orm(string_value = "Tuesday")]
+    Tuesday,
+    #[sea_orm(string_value = "Wednesday")]
+    Wednesday,
+    #[sea_orm(string_value = "Thursday")]
+    Thursday,
+    #[sea_orm(string_value = "Friday")]
+    Friday,
+    #[sea_orm(string_value = "Saturday")]
+    Saturday,
+    #[sea_orm(string_value = "Sunday")]
+    Sunday,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "measurement_kind")]
+pub enum MeasurementKind {
+    #[sea_orm(string_value = "bodyweight")]
+    Bodyweight,
+    #[sea_orm(string_value = "body_fat")]
+    BodyFat,
+    #[sea_orm(string_value = "waist")]
+    Waist,
+    #[sea_orm(string_value = "chest")]
     Chest,
-    #[sea_orm(string_value = "Legs")]
-    Legs,
-    #[sea_orm(string_value = "Shoulders")]
-    Shoulders,
-    #[sea_orm(string_value = "Triceps")]
+    #[sea_orm(string_value = "arm")]
+    Arm,
+    #[sea_orm(string_value = "forearm")]
+    Forearm,
+    #[sea_orm(string_value = "thigh")]
+    Thigh,
+    #[sea_orm(string_value = "calf")]
+    Calf,
+    #[sea_orm(string_value = "hips")]
+    Hips,
+    #[sea_orm(string_value = "neck")]
+    Neck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "lowercase")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "sex")]
+pub enum Sex {
+    #[sea_orm(string_value = "male")]
+    Male,
+    #[sea_orm(string_value = "female")]
+    Female,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "set_type")]
+pub enum SetType {
+    #[sea_orm(string_value = "warm_up")]
+    WarmUp,
+    #[sea_orm(string_value = "working")]
+    Working,
+    #[sea_orm(string_value = "drop_set")]
+    DropSet,
+    #[sea_orm(string_value = "failure")]
+    Failure,
+    #[sea_orm(string_value = "amrap")]
+    Amrap,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "tracking_type")]
+pub enum TrackingType {
+    #[sea_orm(string_value = "weight_reps")]
+    WeightReps,
+    #[sea_orm(string_value = "reps")]
+    Reps,
+    #[sea_orm(string_value = "duration")]
+    Duration,
+    #[sea_orm(string_value = "distance_duration")]
+    DistanceDuration,
+    #[sea_orm(string_value = "weight_distance")]
+    WeightDistance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "muscle")]
+pub enum Muscle {
+    #[sea_orm(string_value = "chest")]
+    Chest,
+    #[sea_orm(string_value = "front_delts")]
+    FrontDelts,
+    #[sea_orm(string_value = "side_delts")]
+    SideDelts,
+    #[sea_orm(string_value = "rear_delts")]
+    RearDelts,
+    #[sea_orm(string_value = "biceps")]
+    Biceps,
+    #[sea_orm(string_value = "triceps")]
     Triceps,
+    #[sea_orm(string_value = "forearms")]
+    Forearms,
+    #[sea_orm(string_value = "lats")]
+    Lats,
+    #[sea_orm(string_value = "upper_back")]
+    UpperBack,
+    #[sea_orm(string_value = "traps")]
+    Traps,
+    #[sea_orm(string_value = "lower_back")]
+    LowerBack,
+    #[sea_orm(string_value = "abs")]
+    Abs,
+    #[sea_orm(string_value = "obliques")]
+    Obliques,
+    #[sea_orm(string_value = "quads")]
+    Quads,
+    #[sea_orm(string_value = "hamstrings")]
+    Hamstrings,
+    #[sea_orm(string_value = "glutes")]
+    Glutes,
+    #[sea_orm(string_value = "adductors")]
+    Adductors,
+    #[sea_orm(string_value = "calves")]
+    Calves,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "muscle_role")]
+pub enum MuscleRole {
+    #[sea_orm(string_value = "primary")]
+    Primary,
+    #[sea_orm(string_value = "secondary")]
+    Secondary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "equipment")]
+pub enum Equipment {
+    #[sea_orm(string_value = "barbell")]
+    Barbell,
+    #[sea_orm(string_value = "dumbbell")]
+    Dumbbell,
+    #[sea_orm(string_value = "cable")]
+    Cable,
+    #[sea_orm(string_value = "machine")]
+    Machine,
+    #[sea_orm(string_value = "bodyweight")]
+    Bodyweight,
+    #[sea_orm(string_value = "kettlebell")]
+    Kettlebell,
+    #[sea_orm(string_value = "bands")]
+    Bands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "set_group_type")]
+pub enum SetGroupType {
+    #[sea_orm(string_value = "superset")]
+    Superset,
+    #[sea_orm(string_value = "circuit")]
+    Circuit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "goal_kind")]
+pub enum GoalKind {
+    #[sea_orm(string_value = "strength")]
+    Strength,
+    #[sea_orm(string_value = "frequency")]
+    Frequency,
+    #[sea_orm(string_value = "measurement")]
+    Measurement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[serde(rename_all = "snake_case")]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "task_recurrence")]
+pub enum TaskRecurrence {
+    #[sea_orm(string_value = "daily")]
+    Daily,
+    #[sea_orm(string_value = "weekly")]
+    Weekly,
 }
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
+use super::sea_orm_active_enums::TaskRecurrence;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -14,11 +15,18 @@ pub struct Model {
     pub description: Option<String>,
     pub deleted_at: Option<DateTimeWithTimeZone>,
     pub is_default: Option<bool>,
+    pub recurrence: Option<TaskRecurrence>,
+    pub recurrence_interval: Option<i32>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub recurrence_days: Option<Json>,
+    pub starts_on: Option<Date>,
     pub user_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::task_completions::Entity")]
+    TaskCompletions,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -29,6 +37,12 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::task_completions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TaskCompletions.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
+use super::sea_orm_active_enums::WeightUnit;
 use sea_orm::entity::prelude::*;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
@@ -18,22 +19,135 @@ pub struct Model {
     pub is_email_verified: bool,
     #[sea_orm(column_type = "Text", nullable)]
     pub email_token: Option<String>,
+    pub preferred_unit: WeightUnit,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::cardio_sessions::Entity")]
+    CardioSessions,
+    #[sea_orm(has_many = "super::categories::Entity")]
+    Categories,
+    #[sea_orm(has_many = "super::equipment_profiles::Entity")]
+    EquipmentProfiles,
     #[sea_orm(has_many = "super::exercises::Entity")]
     Exercises,
+    #[sea_orm(has_many = "super::goals::Entity")]
+    Goals,
+    #[sea_orm(has_many = "super::measurements::Entity")]
+    Measurements,
+    #[sea_orm(has_many = "super::plate_inventories::Entity")]
+    PlateInventories,
+    #[sea_orm(has_many = "super::program_enrollments::Entity")]
+    ProgramEnrollments,
+    #[sea_orm(has_many = "super::programs::Entity")]
+    Programs,
+    #[sea_orm(has_many = "super::routines::Entity")]
+    Routines,
+    #[sea_orm(has_many = "super::stall_alerts::Entity")]
+    StallAlerts,
+    #[sea_orm(has_many = "super::task_completions::Entity")]
+    TaskCompletions,
+    #[sea_orm(has_many = "super::tasks::Entity")]
+    Tasks,
+    #[sea_orm(has_many = "super::user_achievements::Entity")]
+    UserAchievements,
+    #[sea_orm(has_one = "super::user_profiles::Entity")]
+    UserProfiles,
     #[sea_orm(has_many = "super::workout_sets::Entity")]
     WorkoutSets,
 }
 
+impl Related<super::cardio_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CardioSessions.def()
+    }
+}
+
+impl Related<super::categories::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Categories.def()
+    }
+}
+
+impl Related<super::equipment_profiles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EquipmentProfiles.def()
+    }
+}
+
 impl Related<super::exercises::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Exercises.def()
     }
 }
 
+impl Related<super::goals::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Goals.def()
+    }
+}
+
+impl Related<super::measurements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Measurements.def()
+    }
+}
+
+impl Related<super::plate_inventories::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PlateInventories.def()
+    }
+}
+
+impl Related<super::program_enrollments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProgramEnrollments.def()
+    }
+}
+
+impl Related<super::programs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Programs.def()
+    }
+}
+
+impl Related<super::routines::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Routines.def()
+    }
+}
+
+impl Related<super::stall_alerts::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StallAlerts.def()
+    }
+}
+
+impl Related<super::task_completions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TaskCompletions.def()
+    }
+}
+
+impl Related<super::tasks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Tasks.def()
+    }
+}
+
+impl Related<super::user_achievements::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserAchievements.def()
+    }
+}
+
+impl Related<super::user_profiles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserProfiles.def()
+    }
+}
+
 impl Related<super::workout_sets::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::WorkoutSets.def()
@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
-use super::sea_orm_active_enums::Bodypart;
+use super::sea_orm_active_enums::{SetGroupType, SetType, WeightUnit};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -11,16 +11,35 @@ pub struct Model {
     pub id: i32,
     pub date: DateTimeWithTimeZone,
     pub exercise_name: String,
-    pub category: Bodypart,
+    pub category: String,
     pub reps: i32,
     #[sea_orm(column_type = "Double")]
     pub weight: f64,
+    pub weight_unit: WeightUnit,
+    pub set_type: SetType,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub rpe: Option<f64>,
+    pub rir: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub distance_meters: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub pace_seconds_per_km: Option<f64>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
     pub comment: Option<String>,
+    pub order_index: Option<i32>,
+    pub group_id: Option<String>,
+    pub group_type: Option<SetGroupType>,
+    pub rest_seconds: Option<i32>,
+    pub time_under_tension_seconds: Option<i32>,
     pub user_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::cardio_sessions::Entity")]
+    CardioSessions,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -31,6 +50,12 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::cardio_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CardioSessions.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -1,15 +1,36 @@
-use sea_orm::Database;
+use sea_orm::{Database, DatabaseConnection};
 mod database;
 mod routes;
 mod utils;
 use log::warn;
 
-use routes::create_routes;
+use routes::{create_routes, seed_exercise_library, seed_system_categories, spawn_stall_detection};
+
+async fn seed_database(database: &DatabaseConnection) {
+    if seed_system_categories(database).await.is_err() {
+        warn!("could not seed the system categories");
+    }
+    if seed_exercise_library(database).await.is_err() {
+        warn!("could not seed the exercise library");
+    }
+}
+
+// Loads the bundled categories and exercise library without starting the server
+pub async fn seed(database_uri: &str) {
+    let database = Database::connect(database_uri).await.unwrap();
+    seed_database(&database).await;
+}
 
 pub async fn run(database_uri: &str) {
-    let database = Database::connect(database_uri).await;
+    utils::programs::builtin_programs();
+    utils::exercise_library::builtin_library();
+    utils::achievements::builtin_achievements();
+    let database = Database::connect(database_uri).await.unwrap();
+
+    seed_database(&database).await;
+    spawn_stall_detection(database.clone());
 
-    let app = create_routes(database.unwrap()).await;
+    let app = create_routes(database).await;
     let bind_ip = String::from("0.0.0.0:3001");
     warn!("Server started at {}", bind_ip);
 
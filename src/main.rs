@@ -3,7 +3,7 @@ use dotenvy_macro::dotenv;
 use log::{info, warn};
 use simplelog::{Config, ConfigBuilder, LevelFilter, WriteLogger};
 use std::fs::{File, OpenOptions};
-use verifit_rs::run;
+use verifit_rs::{run, seed};
 
 #[tokio::main]
 async fn main() {
@@ -26,5 +26,11 @@ async fn main() {
     warn!("Starting the server");
     warn!("Connecting to {:?}", database_uri);
 
+    // `verifit-rs seed` fills in the built-in data and exits
+    if std::env::args().nth(1).as_deref() == Some("seed") {
+        seed(database_uri).await;
+        return;
+    }
+
     run(database_uri).await;
 }
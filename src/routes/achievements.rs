@@ -0,0 +1,215 @@
+use crate::database::sea_orm_active_enums::{MeasurementKind, SetType, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    measurements, measurements::Entity as Measurements, user_achievements,
+    user_achievements::Entity as UserAchievements, workout_sets,
+    workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::achievements::{builtin_achievements, AchievementStats, StatsBuilder, StatsSet};
+use crate::utils::search::normalize;
+use crate::utils::time::{local_date, parse_timezone, week_start_date};
+use crate::utils::units::convert_weight;
+use axum::{http::StatusCode, Extension, Json};
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Serialize)]
+pub struct ResponseAchievement {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub threshold: f64,
+    pub current: f64,
+    pub progress_percent: f64,
+    pub unlocked_at: Option<DateTimeWithTimeZone>,
+}
+
+// The user's stats as they stand, and when each achievement whose rule they meet was earned
+async fn evaluate_achievements(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<(AchievementStats, HashMap<String, DateTimeWithTimeZone>), StatusCode> {
+    let profile = find_profile_or_default(database, user_id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(database)
+        .await
+        .map_err(|err| {
+            error!("error finding workout sets {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Bodyweight history in kg, oldest first
+    let bodyweights: Vec<(DateTimeWithTimeZone, f64)> = Measurements::find()
+        .filter(measurements::Column::UserId.eq(user_id))
+        .filter(measurements::Column::Kind.eq(MeasurementKind::Bodyweight))
+        .order_by_asc(measurements::Column::Date)
+        .all(database)
+        .await
+        .map_err(|err| {
+            error!("error finding measurements {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(|measurement| {
+            (
+                measurement.date,
+                convert_weight(measurement.value, measurement.unit, WeightUnit::Kg),
+            )
+        })
+        .collect();
+    let profile_bodyweight = profile
+        .bodyweight
+        .map(|bodyweight| convert_weight(bodyweight, profile.bodyweight_unit, WeightUnit::Kg));
+
+    // Latest weigh-in before the set, falling back to the profile and then the first weigh-in
+    let bodyweight_at = |date: &DateTimeWithTimeZone| -> Option<f64> {
+        bodyweights
+            .iter()
+            .take_while(|(measured_at, _)| measured_at <= date)
+            .last()
+            .map(|(_, bodyweight)| *bodyweight)
+            .or(profile_bodyweight)
+            .or(bodyweights.first().map(|(_, bodyweight)| *bodyweight))
+    };
+
+    // An achievement is earned by the set that first brings the stats up to its rule
+    let mut builder = StatsBuilder::default();
+    let mut met_at: HashMap<String, DateTimeWithTimeZone> = HashMap::new();
+    for workout_set in &workout_sets {
+        let weight = convert_weight(workout_set.weight, workout_set.weight_unit, WeightUnit::Kg);
+        let is_lift =
+            workout_set.set_type != SetType::WarmUp && workout_set.reps > 0 && weight > 0.0;
+        let day = local_date(&workout_set.date, timezone);
+        builder.add(StatsSet {
+            day,
+            week_start: week_start_date(day, profile.week_start),
+            exercise_name: normalize(&workout_set.exercise_name),
+            tonnage: weight * workout_set.reps.max(0) as f64,
+            bodyweight_ratio: bodyweight_at(&workout_set.date)
+                .filter(|_| is_lift)
+                .map(|bodyweight| weight / bodyweight),
+        });
+
+        for achievement in builtin_achievements() {
+            if !met_at.contains_key(&achievement.key) && achievement.rule.is_met(&builder.stats) {
+                met_at.insert(achievement.key.clone(), workout_set.date);
+            }
+        }
+    }
+
+    Ok((builder.stats, met_at))
+}
+
+async fn find_unlocked(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<user_achievements::Model>, StatusCode> {
+    UserAchievements::find()
+        .filter(user_achievements::Column::UserId.eq(user_id))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// Checks every rule and stores the ones met for the first time, dated to the set that met
+// them. Runs after sets are logged, so history from before an achievement existed counts too.
+pub async fn unlock_achievements(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<(), StatusCode> {
+    let (_, met_at) = evaluate_achievements(database, user_id).await?;
+    let unlocked_keys: HashSet<String> = find_unlocked(database, user_id)
+        .await?
+        .into_iter()
+        .map(|achievement| achievement.achievement_key)
+        .collect();
+
+    for achievement in builtin_achievements() {
+        if unlocked_keys.contains(&achievement.key) {
+            continue;
+        }
+        let Some(unlocked_at) = met_at.get(&achievement.key) else {
+            continue;
+        };
+
+        let user_achievement = user_achievements::Model {
+            user_id,
+            achievement_key: achievement.key.clone(),
+            unlocked_at: *unlocked_at,
+        };
+        // A concurrent evaluation may have unlocked it first, its row is kept
+        UserAchievements::insert(user_achievement.into_active_model())
+            .on_conflict(
+                OnConflict::columns([
+                    user_achievements::Column::UserId,
+                    user_achievements::Column::AchievementKey,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(database)
+            .await
+            .map_err(|err| {
+                error!("error saving the achievement {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    Ok(())
+}
+
+// Evaluating reads the user's whole history, so logging sets hands it off instead of waiting
+pub fn spawn_achievement_check(database: DatabaseConnection, user_id: i32) {
+    tokio::spawn(async move {
+        // A failed evaluation is picked up again on the next sets
+        if unlock_achievements(&database, user_id).await.is_err() {
+            warn!("could not evaluate achievements for user: {}", user_id);
+        }
+    });
+}
+
+// Every achievement, unlocked or not, in the order of the rules file
+pub async fn get_achievements(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseAchievement>>, StatusCode> {
+    warn!("achievements fetched by user: {}", user.username);
+
+    // Listing only reads, unlocks are stored when sets are logged
+    let (stats, _) = evaluate_achievements(&database, user.id).await?;
+    let unlocked_at: HashMap<String, DateTimeWithTimeZone> = find_unlocked(&database, user.id)
+        .await?
+        .into_iter()
+        .map(|achievement| (achievement.achievement_key, achievement.unlocked_at))
+        .collect();
+
+    let achievements = builtin_achievements()
+        .iter()
+        .map(|achievement| {
+            let threshold = achievement.rule.threshold();
+            let current = achievement.rule.current(&stats);
+            ResponseAchievement {
+                unlocked_at: unlocked_at.get(&achievement.key).copied(),
+                key: achievement.key.clone(),
+                name: achievement.name.clone(),
+                description: achievement.description.clone(),
+                threshold,
+                current,
+                progress_percent: (current / threshold * 100.0).clamp(0.0, 100.0),
+            }
+        })
+        .collect();
+
+    Ok(Json(achievements))
+}
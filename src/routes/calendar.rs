@@ -0,0 +1,124 @@
+use crate::database::users::Model;
+use crate::database::{workout_sets, workout_sets::Entity as WorkoutSets};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::set_filter::SetTypeFilter;
+use crate::utils::streaks::{streaks, Streak};
+use crate::utils::time::{local_date, parse_timezone, week_start_date};
+use axum::{extract::Query, http::StatusCode, Extension, Json};
+use chrono::{Datelike, NaiveDate, Utc};
+use log::warn;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+#[derive(Deserialize, Debug)]
+pub struct CalendarQuery {
+    pub year: Option<i32>,
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CalendarDay {
+    pub date: NaiveDate,
+    pub sets: usize,
+    pub exercises: usize,
+}
+
+#[derive(Serialize)]
+pub struct ResponseCalendar {
+    pub year: i32,
+    pub days: Vec<CalendarDay>,
+    pub training_days: usize,
+    pub rest_days: i64,
+    // Streaks span the whole history, not just the requested year
+    pub daily_streak: Streak,
+    pub weekly_streak: Streak,
+}
+
+#[derive(Default)]
+struct DayTotals<'a> {
+    sets: usize,
+    exercises: HashSet<&'a str>,
+}
+
+pub async fn get_calendar(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(calendar_query): Query<CalendarQuery>,
+) -> Result<Json<ResponseCalendar>, StatusCode> {
+    warn!("calendar fetched by user: {}", user.username);
+
+    // Any set makes a training day unless the client narrows it down
+    let set_type_filter = SetTypeFilter::from_query(
+        calendar_query.include_types.as_deref(),
+        calendar_query.exclude_types.as_deref(),
+        &[],
+    )?;
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let year = calendar_query.year.unwrap_or(today.year());
+
+    let (Some(first_day), Some(last_day)) = (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) else {
+        warn!("invalid calendar year {}", year);
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut totals: BTreeMap<NaiveDate, DayTotals> = BTreeMap::new();
+    for workout_set in workout_sets
+        .iter()
+        .filter(|workout_set| set_type_filter.includes(workout_set.set_type))
+    {
+        let day = totals
+            .entry(local_date(&workout_set.date, timezone))
+            .or_default();
+        day.sets += 1;
+        day.exercises.insert(workout_set.exercise_name.as_str());
+    }
+
+    let training_days: BTreeSet<NaiveDate> = totals.keys().copied().collect();
+    let training_weeks: BTreeSet<NaiveDate> = training_days
+        .iter()
+        .map(|day| week_start_date(*day, profile.week_start))
+        .collect();
+
+    let days: Vec<CalendarDay> = totals
+        .range(first_day..=last_day)
+        .map(|(date, day)| CalendarDay {
+            date: *date,
+            sets: day.sets,
+            exercises: day.exercises.len(),
+        })
+        .collect();
+
+    // Only days that have already happened can be rest days
+    let elapsed_days = if today < first_day {
+        0
+    } else {
+        (today.min(last_day) - first_day).num_days() + 1
+    };
+    let days_trained_so_far = days.iter().filter(|day| day.date <= today).count();
+
+    Ok(Json(ResponseCalendar {
+        year,
+        training_days: days.len(),
+        rest_days: elapsed_days - days_trained_so_far as i64,
+        days,
+        daily_streak: streaks(&training_days, today, 1),
+        weekly_streak: streaks(
+            &training_weeks,
+            week_start_date(today, profile.week_start),
+            7,
+        ),
+    }))
+}
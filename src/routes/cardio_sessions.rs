@@ -0,0 +1,230 @@
+use crate::database::sea_orm_active_enums::SetType;
+use crate::database::users::Model;
+use crate::database::{cardio_sessions, cardio_sessions::Entity as CardioSessions, workout_sets};
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::routes::create_workout_set::{
+    after_sets_created, find_finish_times, find_tracking_types, resolve_timing, validate_set,
+    SetValues,
+};
+use crate::utils::track_import::{parse_track, summarize_track, TrackFormat};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Duration;
+use log::{error, warn};
+use sea_orm::prelude::{DateTimeWithTimeZone, Json as JsonValue};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+// Long rides recorded every second run well past axum's default 2MB
+pub const MAX_TRACK_FILE_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Deserialize, Debug)]
+pub struct ImportQuery {
+    pub exercise_name: Option<String>,
+    pub category: Option<String>,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseCardioSession {
+    pub id: i32,
+    pub workout_set_id: Option<i32>,
+    pub started_at: DateTimeWithTimeZone,
+    pub source: String,
+    pub distance_meters: f64,
+    pub duration_seconds: i32,
+    pub elevation_gain_meters: f64,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
+    pub splits: JsonValue,
+}
+
+fn to_response(cardio_session: cardio_sessions::Model) -> ResponseCardioSession {
+    ResponseCardioSession {
+        id: cardio_session.id,
+        workout_set_id: cardio_session.workout_set_id,
+        started_at: cardio_session.started_at,
+        source: cardio_session.source,
+        distance_meters: cardio_session.distance_meters,
+        duration_seconds: cardio_session.duration_seconds,
+        elevation_gain_meters: cardio_session.elevation_gain_meters,
+        avg_heart_rate: cardio_session.avg_heart_rate,
+        max_heart_rate: cardio_session.max_heart_rate,
+        splits: cardio_session.splits,
+    }
+}
+
+// The request body is the raw GPX or TCX file, the format is detected from its contents
+pub async fn import_cardio_session(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(import_query): Query<ImportQuery>,
+    body: String,
+) -> Result<Json<ResponseCardioSession>, StatusCode> {
+    warn!("cardio session imported by user: {}", user.username);
+
+    let (format, points) = parse_track(&body)?;
+    let summary = summarize_track(format, &points)?;
+    let category_names = find_category_names(&database, user.id).await?;
+    let category = resolve_category(
+        &category_names,
+        import_query.category.as_deref().unwrap_or("Cardio"),
+    )?;
+
+    let source = match summary.format {
+        TrackFormat::Gpx => String::from("gpx"),
+        TrackFormat::Tcx => String::from("tcx"),
+    };
+    let splits = serde_json::to_value(&summary.splits).map_err(|err| {
+        error!("error serializing splits {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let exercise_name = import_query
+        .exercise_name
+        .unwrap_or(String::from("Running"));
+    let pace_seconds_per_km = (summary.distance_meters > 0.0)
+        .then(|| summary.duration_seconds as f64 / (summary.distance_meters / 1000.0));
+    let tracking_types = find_tracking_types(&database, user.id).await?;
+    validate_set(
+        &tracking_types,
+        &SetValues {
+            exercise_name: &exercise_name,
+            reps: 0,
+            weight: 0.0,
+            rpe: None,
+            rir: None,
+            duration_seconds: Some(summary.duration_seconds),
+            distance_meters: Some(summary.distance_meters),
+            pace_seconds_per_km,
+            avg_heart_rate: summary.avg_heart_rate,
+            max_heart_rate: summary.max_heart_rate,
+            rest_seconds: None,
+            time_under_tension_seconds: None,
+        },
+    )?;
+
+    // Sets are logged when they're finished, rest runs from the set before up to the start
+    let finished_at = summary.started_at + Duration::seconds(summary.duration_seconds as i64);
+    let finish_times: Vec<DateTimeWithTimeZone> =
+        find_finish_times(&database, user.id, summary.started_at, summary.started_at)
+            .await?
+            .into_iter()
+            .map(|(_, date)| date)
+            .collect();
+    let (rest_seconds, _) = resolve_timing(
+        &finish_times,
+        finished_at,
+        Some(summary.started_at),
+        None,
+        None,
+        Some(summary.duration_seconds),
+    );
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let workout_set = workout_sets::ActiveModel {
+        exercise_name: Set(exercise_name),
+        date: Set(finished_at),
+        category: Set(category),
+        reps: Set(0),
+        weight: Set(0.0),
+        weight_unit: Set(user.preferred_unit),
+        set_type: Set(SetType::Working),
+        duration_seconds: Set(Some(summary.duration_seconds)),
+        distance_meters: Set(Some(summary.distance_meters)),
+        pace_seconds_per_km: Set(pace_seconds_per_km),
+        avg_heart_rate: Set(summary.avg_heart_rate),
+        max_heart_rate: Set(summary.max_heart_rate),
+        comment: Set(import_query.comment),
+        rest_seconds: Set(rest_seconds),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| {
+        error!("error saving the imported set {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let cardio_session = cardio_sessions::ActiveModel {
+        started_at: Set(summary.started_at),
+        source: Set(source),
+        distance_meters: Set(summary.distance_meters),
+        duration_seconds: Set(summary.duration_seconds),
+        elevation_gain_meters: Set(summary.elevation_gain_meters),
+        avg_heart_rate: Set(summary.avg_heart_rate),
+        max_heart_rate: Set(summary.max_heart_rate),
+        splits: Set(splits),
+        workout_set_id: Set(Some(workout_set.id)),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| {
+        error!("error saving the cardio session {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    after_sets_created(&database, user.id).await;
+
+    Ok(Json(to_response(cardio_session)))
+}
+
+pub async fn get_all_cardio_sessions(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseCardioSession>>, StatusCode> {
+    let cardio_sessions: Vec<ResponseCardioSession> = CardioSessions::find()
+        .filter(cardio_sessions::Column::UserId.eq(user.id))
+        .order_by_asc(cardio_sessions::Column::StartedAt)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(to_response)
+        .collect();
+
+    warn!(
+        "{} cardio sessions fetched by user: {}",
+        cardio_sessions.len(),
+        user.username
+    );
+
+    Ok(Json(cardio_sessions))
+}
+
+pub async fn get_one_cardio_session(
+    Extension(user): Extension<Model>,
+    Path(cardio_session_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ResponseCardioSession>, StatusCode> {
+    warn!("cardio session fetched by user: {}", user.username);
+
+    let cardio_session = CardioSessions::find_by_id(cardio_session_id)
+        .filter(cardio_sessions::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Some(cardio_session) = cardio_session {
+        return Ok(Json(to_response(cardio_session)));
+    }
+
+    Err(StatusCode::NOT_FOUND)
+}
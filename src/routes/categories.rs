@@ -0,0 +1,227 @@
+use crate::database::users::Model;
+use crate::database::{
+    categories, categories::Entity as Categories, exercises, exercises::Entity as Exercises,
+    routine_exercises, routine_exercises::Entity as RoutineExercises, routines, workout_sets,
+    workout_sets::Entity as WorkoutSets,
+};
+use axum::{extract::Path, http::StatusCode, Extension, Json};
+use log::{error, warn};
+use sea_orm::sea_query::JoinType;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+};
+use serde::{Deserialize, Serialize};
+
+// The original seven bodyparts keep their spelling so existing clients keep working
+pub const SYSTEM_CATEGORIES: [&str; 12] = [
+    "Abs",
+    "Back",
+    "Biceps",
+    "Chest",
+    "Legs",
+    "Shoulders",
+    "Triceps",
+    "Glutes",
+    "Calves",
+    "Forearms",
+    "Cardio",
+    "Full Body",
+];
+
+#[derive(Deserialize, Debug)]
+pub struct RequestCategory {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct ResponseCategory {
+    pub id: i32,
+    pub name: String,
+    pub is_system: bool,
+}
+
+// Called on startup, system categories have no owner
+pub async fn seed_system_categories(database: &DatabaseConnection) -> Result<(), StatusCode> {
+    let existing: Vec<String> = Categories::find()
+        .filter(categories::Column::UserId.is_null())
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|category| category.name)
+        .collect();
+
+    let missing: Vec<categories::ActiveModel> = SYSTEM_CATEGORIES
+        .iter()
+        .filter(|name| !existing.iter().any(|existing| existing == *name))
+        .map(|name| categories::ActiveModel {
+            name: Set(name.to_string()),
+            user_id: Set(None),
+            ..Default::default()
+        })
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    warn!("seeding {} system categories", missing.len());
+    Categories::insert_many(missing)
+        .exec(database)
+        .await
+        .map_err(|err| {
+            error!("error seeding system categories {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(())
+}
+
+async fn find_categories(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<categories::Model>, StatusCode> {
+    Categories::find()
+        .filter(
+            Condition::any()
+                .add(categories::Column::UserId.is_null())
+                .add(categories::Column::UserId.eq(user_id)),
+        )
+        .order_by_asc(categories::Column::Id)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// System categories followed by the user's own
+pub async fn find_category_names(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<String>, StatusCode> {
+    Ok(find_categories(database, user_id)
+        .await?
+        .into_iter()
+        .map(|category| category.name)
+        .collect())
+}
+
+// Matching ignores case and surrounding whitespace, the stored spelling is the category's own
+pub fn resolve_category(category_names: &[String], requested: &str) -> Result<String, StatusCode> {
+    category_names
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(requested.trim()))
+        .cloned()
+        .ok_or_else(|| {
+            warn!("unknown category {}", requested);
+            StatusCode::BAD_REQUEST
+        })
+}
+
+pub async fn get_all_categories(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseCategory>>, StatusCode> {
+    let categories: Vec<ResponseCategory> = find_categories(&database, user.id)
+        .await?
+        .into_iter()
+        .map(|category| ResponseCategory {
+            id: category.id,
+            name: category.name,
+            is_system: category.user_id.is_none(),
+        })
+        .collect();
+
+    warn!(
+        "{} categories fetched by user: {}",
+        categories.len(),
+        user.username
+    );
+
+    Ok(Json(categories))
+}
+
+pub async fn create_category(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_category): Json<RequestCategory>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("category created by user: {}", user.username);
+
+    let name = request_category.name.trim().to_string();
+    if name.is_empty() || name.len() > 50 {
+        warn!("invalid category name {}", name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let category_names = find_category_names(&database, user.id).await?;
+    if resolve_category(&category_names, &name).is_ok() {
+        warn!("category {} already exists", name);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let category = categories::ActiveModel {
+        name: Set(name),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving the category {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(category.id))
+}
+
+// Sets and exercises store the category by name, so one still in use can't be deleted
+pub async fn delete_category(
+    Extension(user): Extension<Model>,
+    Path(category_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("category deleted by user: {}", user.username);
+
+    let category = Categories::find_by_id(category_id)
+        .filter(categories::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let used_by_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::Category.eq(category.name.clone()))
+        .count(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let used_by_exercises = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user.id))
+        .filter(exercises::Column::Bodypart.eq(category.name.clone()))
+        .count(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let used_by_routines = RoutineExercises::find()
+        .join(
+            JoinType::InnerJoin,
+            routine_exercises::Relation::Routines.def(),
+        )
+        .filter(routines::Column::UserId.eq(user.id))
+        .filter(routine_exercises::Column::Category.eq(category.name.clone()))
+        .count(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if used_by_sets + used_by_exercises + used_by_routines > 0 {
+        warn!("category {} is still in use", category.name);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    Categories::delete_by_id(category.id)
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
@@ -1,33 +1,59 @@
 use crate::database::exercises;
-use crate::database::sea_orm_active_enums::Bodypart;
+use crate::database::sea_orm_active_enums::{Equipment, TrackingType};
 use crate::database::users;
 use crate::database::users::Entity as Users;
 use crate::database::users::Model;
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::utils::progression::{find_strategy, strategy_names, DEFAULT_STRATEGY};
 use axum::headers::authorization::Bearer;
 use axum::headers::Authorization;
 use axum::http::StatusCode;
 use axum::Extension;
 use axum::Json;
 use axum::TypedHeader;
+use log::warn;
 use sea_orm::ColumnTrait;
 use sea_orm::EntityTrait;
 use sea_orm::IntoActiveModel;
 use sea_orm::QueryFilter;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
 use serde::Deserialize;
-
-#[derive(Deserialize)]
-pub struct RequestTask {
-    title: String,
-    priority: Option<String>,
-    description: Option<String>,
-}
+use serde_json::Value;
 
 #[derive(Deserialize)]
 pub struct RequestExercise {
     name: String,
-    bodypart: Bodypart,
+    bodypart: String,
     isfavorite: bool,
+    tracking_type: Option<TrackingType>,
+    equipment: Option<Equipment>,
+    progression_strategy: Option<String>,
+    progression_settings: Option<Value>,
+}
+
+// Unknown strategies or settings they can't read are rejected up front
+pub fn validate_progression(
+    progression_strategy: Option<String>,
+    progression_settings: Option<&Value>,
+) -> Result<String, StatusCode> {
+    let progression_strategy = progression_strategy.unwrap_or(String::from(DEFAULT_STRATEGY));
+    let Some(strategy) = find_strategy(&progression_strategy) else {
+        warn!(
+            "unknown progression strategy {}, expected one of {:?}",
+            progression_strategy,
+            strategy_names()
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    strategy
+        .validate_settings(progression_settings)
+        .map_err(|reason| {
+            warn!("invalid progression settings: {}", reason);
+            StatusCode::BAD_REQUEST
+        })?;
+
+    Ok(progression_strategy)
 }
 
 pub async fn create_exercise(
@@ -35,12 +61,24 @@ pub async fn create_exercise(
     Extension(database): Extension<DatabaseConnection>,
     Json(request_exercise): Json<RequestExercise>,
 ) -> Result<(), StatusCode> {
+    let progression_strategy = validate_progression(
+        request_exercise.progression_strategy,
+        request_exercise.progression_settings.as_ref(),
+    )?;
+    let category_names = find_category_names(&database, user.id).await?;
+    let bodypart = resolve_category(&category_names, &request_exercise.bodypart)?;
     let user = user.into_active_model();
 
     let new_exercise = exercises::ActiveModel {
         name: Set(request_exercise.name),
-        bodypart: Set(request_exercise.bodypart),
+        bodypart: Set(bodypart),
         isfavorite: Set(request_exercise.isfavorite),
+        tracking_type: Set(request_exercise
+            .tracking_type
+            .unwrap_or(TrackingType::WeightReps)),
+        equipment: Set(request_exercise.equipment),
+        progression_strategy: Set(progression_strategy),
+        progression_settings: Set(request_exercise.progression_settings),
         user_id: Set(Some(user.id.unwrap())),
         ..Default::default()
     };
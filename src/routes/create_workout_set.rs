@@ -1,7 +1,19 @@
-use crate::database::sea_orm_active_enums::Bodypart;
+use crate::database::exercises::{self, Entity as Exercises};
+use crate::database::sea_orm_active_enums::{SetGroupType, SetType, TrackingType, WeightUnit};
 use crate::database::users::Entity as Users;
 use crate::database::users::{self, Model};
-use crate::database::workout_sets;
+use crate::database::workout_sets::{self, Entity as WorkoutSets};
+use crate::routes::achievements::spawn_achievement_check;
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::routes::goals::{complete_reached_goals, SET_GOAL_KINDS};
+use crate::routes::programs::advance_enrollments;
+use crate::utils::set_timing::{
+    max_gap, previous_finish, resolve_rest, resolve_time_under_tension,
+};
+use crate::utils::set_validation::{
+    resolve_group_type, resolve_pace, validate_cardio, validate_effort, validate_group_types,
+    validate_heart_rate, validate_timing, validate_tracking,
+};
 use axum::headers::authorization::Bearer;
 use axum::headers::Authorization;
 use axum::http::StatusCode;
@@ -11,18 +23,201 @@ use sea_orm::EntityTrait;
 use sea_orm::QueryFilter;
 use sea_orm::{prelude::DateTimeWithTimeZone, DatabaseConnection};
 use sea_orm::{ActiveModelTrait, Set};
-use sea_orm::{ColumnTrait, IntoActiveModel};
+use sea_orm::{ColumnTrait, ConnectionTrait, IntoActiveModel};
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Barrier;
 
 #[derive(Deserialize)]
 pub struct RequestWorkoutSet {
     pub exercise_name: String,
     pub date: DateTimeWithTimeZone,
-    pub category: Bodypart,
+    pub category: String,
+    #[serde(default)]
     pub reps: i32,
+    #[serde(default)]
     pub weight: f64,
+    pub unit: Option<WeightUnit>,
+    pub set_type: Option<SetType>,
+    pub rpe: Option<f64>,
+    pub rir: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub distance_meters: Option<f64>,
+    pub pace_seconds_per_km: Option<f64>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
     pub comment: Option<String>,
+    // Position within the workout and the superset or circuit the set belongs to
+    pub order_index: Option<i32>,
+    pub group_id: Option<String>,
+    pub group_type: Option<SetGroupType>,
+    // When the set was started, rest and time under tension are worked out from it if not given
+    pub started_at: Option<DateTimeWithTimeZone>,
+    pub rest_seconds: Option<i32>,
+    pub time_under_tension_seconds: Option<i32>,
+}
+
+// Exercises the user hasn't defined are treated as plain weight and reps lifts
+pub async fn find_tracking_types(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<HashMap<String, TrackingType>, StatusCode> {
+    let tracking_types = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user_id))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|exercise| (exercise.name, exercise.tracking_type))
+        .collect();
+
+    Ok(tracking_types)
+}
+
+// What a set is checked against. Edits merge the request over the stored set first, so the
+// fields a client leaves out are checked as they are stored.
+pub struct SetValues<'a> {
+    pub exercise_name: &'a str,
+    pub reps: i32,
+    pub weight: f64,
+    pub rpe: Option<f64>,
+    pub rir: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub distance_meters: Option<f64>,
+    pub pace_seconds_per_km: Option<f64>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
+    pub rest_seconds: Option<i32>,
+    pub time_under_tension_seconds: Option<i32>,
+}
+
+impl<'a> From<&'a RequestWorkoutSet> for SetValues<'a> {
+    fn from(request_workout_set: &'a RequestWorkoutSet) -> Self {
+        SetValues {
+            exercise_name: &request_workout_set.exercise_name,
+            reps: request_workout_set.reps,
+            weight: request_workout_set.weight,
+            rpe: request_workout_set.rpe,
+            rir: request_workout_set.rir,
+            duration_seconds: request_workout_set.duration_seconds,
+            distance_meters: request_workout_set.distance_meters,
+            pace_seconds_per_km: request_workout_set.pace_seconds_per_km,
+            avg_heart_rate: request_workout_set.avg_heart_rate,
+            max_heart_rate: request_workout_set.max_heart_rate,
+            rest_seconds: request_workout_set.rest_seconds,
+            time_under_tension_seconds: request_workout_set.time_under_tension_seconds,
+        }
+    }
+}
+
+impl<'a> From<&'a workout_sets::Model> for SetValues<'a> {
+    fn from(workout_set: &'a workout_sets::Model) -> Self {
+        SetValues {
+            exercise_name: &workout_set.exercise_name,
+            reps: workout_set.reps,
+            weight: workout_set.weight,
+            rpe: workout_set.rpe,
+            rir: workout_set.rir,
+            duration_seconds: workout_set.duration_seconds,
+            distance_meters: workout_set.distance_meters,
+            pace_seconds_per_km: workout_set.pace_seconds_per_km,
+            avg_heart_rate: workout_set.avg_heart_rate,
+            max_heart_rate: workout_set.max_heart_rate,
+            rest_seconds: workout_set.rest_seconds,
+            time_under_tension_seconds: workout_set.time_under_tension_seconds,
+        }
+    }
+}
+
+pub fn validate_set(
+    tracking_types: &HashMap<String, TrackingType>,
+    set_values: &SetValues,
+) -> Result<(), StatusCode> {
+    let tracking_type = tracking_types
+        .get(set_values.exercise_name)
+        .copied()
+        .unwrap_or(TrackingType::WeightReps);
+
+    validate_effort(set_values.rpe, set_values.rir)?;
+    validate_heart_rate(set_values.avg_heart_rate, set_values.max_heart_rate)?;
+    validate_timing(
+        set_values.rest_seconds,
+        set_values.time_under_tension_seconds,
+    )?;
+    validate_cardio(
+        set_values.duration_seconds,
+        set_values.distance_meters,
+        set_values.pace_seconds_per_km,
+    )?;
+    validate_tracking(
+        tracking_type,
+        set_values.reps,
+        set_values.weight,
+        set_values.duration_seconds,
+        set_values.distance_meters,
+    )
+}
+
+// Ids and finish times of the user's sets from shortly before `earliest` up to `latest`
+pub async fn find_finish_times<C: ConnectionTrait>(
+    database: &C,
+    user_id: i32,
+    earliest: DateTimeWithTimeZone,
+    latest: DateTimeWithTimeZone,
+) -> Result<Vec<(i32, DateTimeWithTimeZone)>, StatusCode> {
+    let finish_times = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .filter(workout_sets::Column::Date.gte(earliest - max_gap()))
+        .filter(workout_sets::Column::Date.lte(latest))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|workout_set| (workout_set.id, workout_set.date))
+        .collect();
+
+    Ok(finish_times)
+}
+
+// Rest and time under tension for a set given the finish times of the sets around it
+pub fn resolve_timing(
+    finish_times: &[DateTimeWithTimeZone],
+    date: DateTimeWithTimeZone,
+    started_at: Option<DateTimeWithTimeZone>,
+    rest_seconds: Option<i32>,
+    time_under_tension_seconds: Option<i32>,
+    duration_seconds: Option<i32>,
+) -> (Option<i32>, Option<i32>) {
+    let time_under_tension =
+        resolve_time_under_tension(time_under_tension_seconds, started_at, date);
+    let rest = resolve_rest(
+        rest_seconds,
+        previous_finish(finish_times, date),
+        started_at,
+        date,
+        time_under_tension.or(duration_seconds),
+    );
+    (rest, time_under_tension)
+}
+
+// Everything that follows new sets being logged, wherever they come from. A failure here
+// shouldn't fail the sets, it's picked up again on the next ones.
+pub async fn after_sets_created(database: &DatabaseConnection, user_id: i32) {
+    spawn_achievement_check(database.clone(), user_id);
+
+    if advance_enrollments(database, user_id).await.is_err() {
+        warn!(
+            "could not advance program enrollments for user: {}",
+            user_id
+        );
+    }
+
+    if complete_reached_goals(database, user_id, &SET_GOAL_KINDS)
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user_id);
+    }
 }
 
 pub async fn create_workout_set(
@@ -32,21 +227,72 @@ pub async fn create_workout_set(
 ) -> Result<Json<i32>, StatusCode> {
     warn!("set created by user: {}", user.username);
 
+    let tracking_types = find_tracking_types(&database, user.id).await?;
+    validate_set(&tracking_types, &SetValues::from(&request_workout_set))?;
+    let category_names = find_category_names(&database, user.id).await?;
+    let category = resolve_category(&category_names, &request_workout_set.category)?;
+    let group_type = resolve_group_type(
+        request_workout_set.order_index,
+        request_workout_set.group_id.as_deref(),
+        request_workout_set.group_type,
+    )?;
+
+    let finish_times: Vec<DateTimeWithTimeZone> = find_finish_times(
+        &database,
+        user.id,
+        request_workout_set.date,
+        request_workout_set.date,
+    )
+    .await?
+    .into_iter()
+    .map(|(_, date)| date)
+    .collect();
+    let (rest_seconds, time_under_tension_seconds) = resolve_timing(
+        &finish_times,
+        request_workout_set.date,
+        request_workout_set.started_at,
+        request_workout_set.rest_seconds,
+        request_workout_set.time_under_tension_seconds,
+        request_workout_set.duration_seconds,
+    );
+
+    let preferred_unit = user.preferred_unit;
+    let user_id = user.id;
     let user = user.into_active_model();
 
     let new_workout_set = workout_sets::ActiveModel {
         exercise_name: Set(request_workout_set.exercise_name),
         date: Set(request_workout_set.date),
-        category: Set(request_workout_set.category),
+        category: Set(category),
         reps: Set(request_workout_set.reps),
         weight: Set(request_workout_set.weight),
+        weight_unit: Set(request_workout_set.unit.unwrap_or(preferred_unit)),
+        set_type: Set(request_workout_set.set_type.unwrap_or(SetType::Working)),
+        rpe: Set(request_workout_set.rpe),
+        rir: Set(request_workout_set.rir),
+        duration_seconds: Set(request_workout_set.duration_seconds),
+        distance_meters: Set(request_workout_set.distance_meters),
+        pace_seconds_per_km: Set(resolve_pace(
+            request_workout_set.pace_seconds_per_km,
+            request_workout_set.duration_seconds,
+            request_workout_set.distance_meters,
+        )),
+        avg_heart_rate: Set(request_workout_set.avg_heart_rate),
+        max_heart_rate: Set(request_workout_set.max_heart_rate),
         user_id: Set(Some(user.id.unwrap())),
         comment: Set(request_workout_set.comment),
+        order_index: Set(request_workout_set.order_index),
+        group_id: Set(request_workout_set.group_id),
+        group_type: Set(group_type),
+        rest_seconds: Set(rest_seconds),
+        time_under_tension_seconds: Set(time_under_tension_seconds),
         ..Default::default()
     };
 
     let result = new_workout_set.save(&database).await.unwrap();
 
+    after_sets_created(&database, user_id).await;
+
     Ok(Json(result.id.unwrap()))
 }
 
@@ -63,18 +309,83 @@ pub async fn create_workout_sets(
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    let preferred_unit = user.preferred_unit;
     let user_id = user.into_active_model().id.unwrap();
+    let tracking_types = find_tracking_types(&database, user_id).await?;
+    let category_names = find_category_names(&database, user_id).await?;
     let mut workout_sets_to_insert = Vec::new();
 
-    for request_workout_set in request_workout_set_vector {
+    let group_types = request_workout_set_vector
+        .iter()
+        .map(|request_workout_set| {
+            resolve_group_type(
+                request_workout_set.order_index,
+                request_workout_set.group_id.as_deref(),
+                request_workout_set.group_type,
+            )
+        })
+        .collect::<Result<Vec<Option<SetGroupType>>, StatusCode>>()?;
+    validate_group_types(
+        request_workout_set_vector
+            .iter()
+            .zip(&group_types)
+            .filter_map(|(request_workout_set, group_type)| {
+                Some((request_workout_set.group_id.as_deref()?, (*group_type)?))
+            }),
+    )?;
+
+    // Rest is measured against the sets already logged as well as the ones in this batch
+    let mut finish_times: Vec<DateTimeWithTimeZone> = request_workout_set_vector
+        .iter()
+        .map(|request_workout_set| request_workout_set.date)
+        .collect();
+    if let (Some(earliest), Some(latest)) = (
+        finish_times.iter().min().copied(),
+        finish_times.iter().max().copied(),
+    ) {
+        let logged_finish_times = find_finish_times(&database, user_id, earliest, latest).await?;
+        finish_times.extend(logged_finish_times.into_iter().map(|(_, date)| date));
+    }
+
+    for (request_workout_set, group_type) in request_workout_set_vector.into_iter().zip(group_types)
+    {
+        validate_set(&tracking_types, &SetValues::from(&request_workout_set))?;
+        let category = resolve_category(&category_names, &request_workout_set.category)?;
+        let (rest_seconds, time_under_tension_seconds) = resolve_timing(
+            &finish_times,
+            request_workout_set.date,
+            request_workout_set.started_at,
+            request_workout_set.rest_seconds,
+            request_workout_set.time_under_tension_seconds,
+            request_workout_set.duration_seconds,
+        );
+
         let new_workout_set = workout_sets::ActiveModel {
             exercise_name: Set(request_workout_set.exercise_name),
             date: Set(request_workout_set.date),
-            category: Set(request_workout_set.category),
+            category: Set(category),
             reps: Set(request_workout_set.reps),
             weight: Set(request_workout_set.weight),
+            weight_unit: Set(request_workout_set.unit.unwrap_or(preferred_unit)),
+            set_type: Set(request_workout_set.set_type.unwrap_or(SetType::Working)),
+            rpe: Set(request_workout_set.rpe),
+            rir: Set(request_workout_set.rir),
+            duration_seconds: Set(request_workout_set.duration_seconds),
+            distance_meters: Set(request_workout_set.distance_meters),
+            pace_seconds_per_km: Set(resolve_pace(
+                request_workout_set.pace_seconds_per_km,
+                request_workout_set.duration_seconds,
+                request_workout_set.distance_meters,
+            )),
+            avg_heart_rate: Set(request_workout_set.avg_heart_rate),
+            max_heart_rate: Set(request_workout_set.max_heart_rate),
             user_id: Set(Some(user_id)),
             comment: Set(request_workout_set.comment),
+            order_index: Set(request_workout_set.order_index),
+            group_id: Set(request_workout_set.group_id),
+            group_type: Set(group_type),
+            rest_seconds: Set(rest_seconds),
+            time_under_tension_seconds: Set(time_under_tension_seconds),
             ..Default::default()
         };
         workout_sets_to_insert.push(new_workout_set);
@@ -85,5 +396,7 @@ pub async fn create_workout_sets(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    after_sets_created(&database, user_id).await;
+
     Ok(())
 }
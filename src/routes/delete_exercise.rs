@@ -1,5 +1,5 @@
 use crate::database::users::Model;
-use crate::database::{exercises, exercises::Entity as Exercises, sea_orm_active_enums::Bodypart};
+use crate::database::{exercises, exercises::Entity as Exercises};
 use axum::{extract::Path, http::StatusCode, Extension};
 use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
@@ -1,6 +1,4 @@
-use crate::database::{
-    sea_orm_active_enums::Bodypart, users::Model, workout_sets, workout_sets::Entity as Sets,
-};
+use crate::database::{users::Model, workout_sets, workout_sets::Entity as Sets};
 use axum::Json;
 use axum::{extract::Path, http::StatusCode, Extension};
 use log::warn;
@@ -0,0 +1,47 @@
+use crate::utils::email_templates::{render_email, EmailTemplate};
+use axum::extract::{Path, Query};
+use axum::http::{Response, StatusCode};
+use log::{error, warn};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct PreviewQuery {
+    pub locale: Option<String>,
+    // html (the default) or text
+    pub format: Option<String>,
+}
+
+// Renders a template with sample values so copy and layout changes can be checked in a browser.
+// Only routed in debug builds.
+pub async fn preview_email(
+    Path(template_name): Path<String>,
+    Query(query): Query<PreviewQuery>,
+) -> Result<Response<String>, StatusCode> {
+    warn!("email preview of {}", template_name);
+
+    let template = EmailTemplate::from_name(&template_name).ok_or(StatusCode::NOT_FOUND)?;
+    let email = render_email(
+        template,
+        query.locale.as_deref(),
+        &template.sample_context(),
+    )
+    .map_err(|err| {
+        error!("error rendering the {} email {}", template_name, err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let (content_type, body) = match query.format.as_deref() {
+        None | Some("html") => ("text/html; charset=utf-8", email.html),
+        Some("text") => (
+            "text/plain; charset=utf-8",
+            format!("Subject: {}\n\n{}", email.subject, email.text),
+        ),
+        Some(_) => return Err(StatusCode::BAD_REQUEST),
+    };
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", content_type)
+        .body(body)
+        .unwrap())
+}
@@ -0,0 +1,423 @@
+use crate::database::sea_orm_active_enums::{Equipment, Muscle, MuscleRole};
+use crate::database::users::Model;
+use crate::database::{
+    equipment_profiles, equipment_profiles::Entity as EquipmentProfiles,
+    exercise_muscles::Entity as ExerciseMuscles, exercises, exercises::Entity as Exercises,
+    library_exercises::Entity as LibraryExercises,
+};
+use crate::routes::exercise_library::library_muscles;
+use crate::utils::muscles::{fallback_muscles, muscle_similarity};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use log::{error, warn};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Iterable,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Deserialize, Debug)]
+pub struct RequestEquipmentProfile {
+    pub name: String,
+    pub equipment: Vec<Equipment>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseEquipmentProfile {
+    pub id: i32,
+    pub name: String,
+    pub equipment: Vec<Equipment>,
+    pub is_active: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct SubstitutesQuery {
+    // Defaults to the active profile
+    pub profile_id: Option<i32>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseSubstitute {
+    pub name: String,
+    pub bodypart: String,
+    pub equipment: Option<Equipment>,
+    pub exercise_id: Option<i32>,
+    pub library_exercise_id: Option<i32>,
+    pub similarity: f64,
+}
+
+struct Candidate {
+    name: String,
+    bodypart: String,
+    equipment: Option<Equipment>,
+    exercise_id: Option<i32>,
+    library_exercise_id: Option<i32>,
+    muscles: Vec<(Muscle, MuscleRole)>,
+}
+
+fn to_response(profile: equipment_profiles::Model) -> ResponseEquipmentProfile {
+    ResponseEquipmentProfile {
+        id: profile.id,
+        name: profile.name,
+        equipment: serde_json::from_value(profile.equipment).unwrap_or_default(),
+        is_active: profile.is_active,
+    }
+}
+
+fn validate_profile(
+    request_profile: &RequestEquipmentProfile,
+) -> Result<(String, Vec<Equipment>), StatusCode> {
+    let name = request_profile.name.trim().to_string();
+    if name.is_empty() || name.len() > 50 {
+        warn!("invalid equipment profile name {}", name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let equipment = Equipment::iter()
+        .filter(|equipment| request_profile.equipment.contains(equipment))
+        .collect();
+    Ok((name, equipment))
+}
+
+async fn find_profile(
+    database: &DatabaseConnection,
+    user_id: i32,
+    profile_id: i32,
+) -> Result<equipment_profiles::Model, StatusCode> {
+    EquipmentProfiles::find_by_id(profile_id)
+        .filter(equipment_profiles::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// The given profile or else the active one. Users without any profile aren't restricted.
+pub async fn find_available_equipment(
+    database: &DatabaseConnection,
+    user_id: i32,
+    profile_id: Option<i32>,
+) -> Result<Option<Vec<Equipment>>, StatusCode> {
+    let profile = match profile_id {
+        Some(profile_id) => Some(find_profile(database, user_id, profile_id).await?),
+        None => EquipmentProfiles::find()
+            .filter(equipment_profiles::Column::UserId.eq(user_id))
+            .filter(equipment_profiles::Column::IsActive.eq(true))
+            .one(database)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    };
+
+    Ok(profile.map(|profile| serde_json::from_value(profile.equipment).unwrap_or_default()))
+}
+
+// Exercises created before equipment tagging have none and are always kept
+pub fn is_available(
+    available_equipment: &Option<Vec<Equipment>>,
+    equipment: Option<Equipment>,
+) -> bool {
+    match (available_equipment, equipment) {
+        (Some(available_equipment), Some(equipment)) => available_equipment.contains(&equipment),
+        _ => true,
+    }
+}
+
+pub async fn get_equipment_profiles(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseEquipmentProfile>>, StatusCode> {
+    warn!("equipment profiles fetched by user: {}", user.username);
+
+    let profiles = EquipmentProfiles::find()
+        .filter(equipment_profiles::Column::UserId.eq(user.id))
+        .order_by_asc(equipment_profiles::Column::Id)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(to_response)
+        .collect();
+
+    Ok(Json(profiles))
+}
+
+// The first profile a user creates becomes the active one
+pub async fn create_equipment_profile(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_profile): Json<RequestEquipmentProfile>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("equipment profile created by user: {}", user.username);
+
+    let (name, equipment) = validate_profile(&request_profile)?;
+    let has_active_profile = find_available_equipment(&database, user.id, None)
+        .await?
+        .is_some();
+
+    let profile = equipment_profiles::ActiveModel {
+        name: Set(name),
+        equipment: Set(json!(equipment)),
+        is_active: Set(!has_active_profile),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving the equipment profile {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(profile.id))
+}
+
+pub async fn update_equipment_profile(
+    Extension(user): Extension<Model>,
+    Path(profile_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_profile): Json<RequestEquipmentProfile>,
+) -> Result<(), StatusCode> {
+    warn!("equipment profile updated by user: {}", user.username);
+
+    let (name, equipment) = validate_profile(&request_profile)?;
+    let mut profile = find_profile(&database, user.id, profile_id)
+        .await?
+        .into_active_model();
+    profile.name = Set(name);
+    profile.equipment = Set(json!(equipment));
+    profile.update(&database).await.map_err(|err| {
+        error!("error saving the equipment profile {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
+pub async fn delete_equipment_profile(
+    Extension(user): Extension<Model>,
+    Path(profile_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("equipment profile deleted by user: {}", user.username);
+
+    let profile = find_profile(&database, user.id, profile_id).await?;
+    EquipmentProfiles::delete_by_id(profile.id)
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+// Switching between e.g. a home gym and a commercial gym, only one profile is active at a time
+pub async fn activate_equipment_profile(
+    Extension(user): Extension<Model>,
+    Path(profile_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("equipment profile activated by user: {}", user.username);
+
+    let profile = find_profile(&database, user.id, profile_id).await?;
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    EquipmentProfiles::update_many()
+        .col_expr(equipment_profiles::Column::IsActive, Expr::value(false))
+        .filter(equipment_profiles::Column::UserId.eq(user.id))
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut profile = profile.into_active_model();
+    profile.is_active = Set(true);
+    profile.update(&txn).await.map_err(|err| {
+        error!("error saving the equipment profile {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+// The user's exercises plus the library exercises they have no copy of, with the muscles each
+// one works: its own mapping, else the library's, else its category's
+async fn find_candidates(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<Candidate>, StatusCode> {
+    let library = LibraryExercises::find()
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let user_exercises = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user_id))
+        .find_with_related(ExerciseMuscles)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let library_by_id: HashMap<i32, _> = library
+        .iter()
+        .map(|library_exercise| (library_exercise.id, library_exercise))
+        .collect();
+    let user_names: HashSet<String> = user_exercises
+        .iter()
+        .map(|(exercise, _)| exercise.name.clone())
+        .collect();
+    let linked_ids: HashSet<i32> = user_exercises
+        .iter()
+        .filter_map(|(exercise, _)| exercise.library_exercise_id)
+        .collect();
+
+    let mut candidates = Vec::new();
+    for (exercise, exercise_muscles) in user_exercises {
+        let library_exercise = exercise
+            .library_exercise_id
+            .and_then(|library_exercise_id| library_by_id.get(&library_exercise_id));
+
+        let muscles = if !exercise_muscles.is_empty() {
+            exercise_muscles
+                .into_iter()
+                .map(|exercise_muscle| (exercise_muscle.muscle, exercise_muscle.role))
+                .collect()
+        } else if let Some(library_exercise) = library_exercise {
+            library_muscles(library_exercise)
+        } else {
+            fallback_muscles(&exercise.bodypart)
+                .iter()
+                .map(|muscle| (*muscle, MuscleRole::Primary))
+                .collect()
+        };
+
+        candidates.push(Candidate {
+            equipment: exercise
+                .equipment
+                .or(library_exercise.map(|library_exercise| library_exercise.equipment)),
+            name: exercise.name,
+            bodypart: exercise.bodypart,
+            exercise_id: Some(exercise.id),
+            library_exercise_id: exercise.library_exercise_id,
+            muscles,
+        });
+    }
+
+    for library_exercise in &library {
+        if linked_ids.contains(&library_exercise.id) || user_names.contains(&library_exercise.name)
+        {
+            continue;
+        }
+        candidates.push(Candidate {
+            name: library_exercise.name.clone(),
+            bodypart: library_exercise.category.clone(),
+            equipment: Some(library_exercise.equipment),
+            exercise_id: None,
+            library_exercise_id: Some(library_exercise.id),
+            muscles: library_muscles(library_exercise),
+        });
+    }
+
+    Ok(candidates)
+}
+
+// Alternatives that hit the same primary muscles with the equipment at hand, closest first
+async fn find_substitutes(
+    database: &DatabaseConnection,
+    user_id: i32,
+    substitutes_query: SubstitutesQuery,
+    is_target: impl Fn(&Candidate) -> bool,
+) -> Result<Vec<ResponseSubstitute>, StatusCode> {
+    let limit = substitutes_query.limit.unwrap_or(10);
+    if !(1..=50).contains(&limit) {
+        warn!("invalid substitute limit {}", limit);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let available_equipment =
+        find_available_equipment(database, user_id, substitutes_query.profile_id).await?;
+    let mut candidates = find_candidates(database, user_id).await?;
+    let Some(target_index) = candidates.iter().position(is_target) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let target = candidates.remove(target_index);
+    let target_primary: Vec<Muscle> = target
+        .muscles
+        .iter()
+        .filter(|(_, role)| *role == MuscleRole::Primary)
+        .map(|(muscle, _)| *muscle)
+        .collect();
+
+    let mut substitutes: Vec<ResponseSubstitute> = candidates
+        .into_iter()
+        .filter(|candidate| {
+            candidate.name != target.name
+                && is_available(&available_equipment, candidate.equipment)
+                && candidate.muscles.iter().any(|(muscle, role)| {
+                    *role == MuscleRole::Primary && target_primary.contains(muscle)
+                })
+        })
+        .map(|candidate| ResponseSubstitute {
+            similarity: muscle_similarity(&target.muscles, &candidate.muscles),
+            name: candidate.name,
+            bodypart: candidate.bodypart,
+            equipment: candidate.equipment,
+            exercise_id: candidate.exercise_id,
+            library_exercise_id: candidate.library_exercise_id,
+        })
+        .collect();
+
+    substitutes.sort_by(|left, right| {
+        right
+            .similarity
+            .total_cmp(&left.similarity)
+            .then_with(|| left.name.cmp(&right.name))
+    });
+    substitutes.truncate(limit);
+
+    Ok(substitutes)
+}
+
+pub async fn get_substitutes(
+    Extension(user): Extension<Model>,
+    Path(exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(substitutes_query): Query<SubstitutesQuery>,
+) -> Result<Json<Vec<ResponseSubstitute>>, StatusCode> {
+    warn!("exercise substitutes fetched by user: {}", user.username);
+
+    let substitutes = find_substitutes(&database, user.id, substitutes_query, |candidate| {
+        candidate.exercise_id == Some(exercise_id)
+    })
+    .await?;
+
+    Ok(Json(substitutes))
+}
+
+// Library exercises the user has taken over are looked up through their own copy
+pub async fn get_library_substitutes(
+    Extension(user): Extension<Model>,
+    Path(library_exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(substitutes_query): Query<SubstitutesQuery>,
+) -> Result<Json<Vec<ResponseSubstitute>>, StatusCode> {
+    warn!("library substitutes fetched by user: {}", user.username);
+
+    let substitutes = find_substitutes(&database, user.id, substitutes_query, |candidate| {
+        candidate.library_exercise_id == Some(library_exercise_id)
+    })
+    .await?;
+
+    Ok(Json(substitutes))
+}
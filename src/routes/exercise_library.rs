@@ -0,0 +1,395 @@
+use crate::database::sea_orm_active_enums::{Equipment, Muscle, MuscleRole, TrackingType};
+use crate::database::users::Model;
+use crate::database::{
+    exercise_muscles, exercise_muscles::Entity as ExerciseMuscles, exercises,
+    exercises::Entity as Exercises, library_exercises,
+    library_exercises::Entity as LibraryExercises,
+};
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::routes::equipment::{find_available_equipment, is_available};
+use crate::utils::exercise_library::{builtin_library, LibraryEntry};
+use crate::utils::progression::DEFAULT_STRATEGY;
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use log::{error, warn};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct LibraryQuery {
+    pub category: Option<String>,
+    pub equipment: Option<Equipment>,
+    #[serde(default)]
+    pub favorites_only: bool,
+    // Only exercises doable with the active equipment profile
+    #[serde(default)]
+    pub available_only: bool,
+}
+
+// Every field is optional, only the ones given are layered over the library defaults
+#[derive(Deserialize, Debug)]
+pub struct RequestLibraryOverride {
+    pub name: Option<String>,
+    pub bodypart: Option<String>,
+    pub isfavorite: Option<bool>,
+    pub tracking_type: Option<TrackingType>,
+    pub equipment: Option<Equipment>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseLibraryExercise {
+    pub id: i32,
+    pub key: String,
+    pub name: String,
+    pub bodypart: String,
+    pub equipment: Equipment,
+    pub tracking_type: TrackingType,
+    pub primary_muscles: Vec<Muscle>,
+    pub secondary_muscles: Vec<Muscle>,
+    pub aliases: Vec<String>,
+    pub isfavorite: bool,
+    // The user's own exercise when they've favorited or customized this one
+    pub exercise_id: Option<i32>,
+}
+
+fn to_active_model(entry: &LibraryEntry) -> library_exercises::ActiveModel {
+    library_exercises::ActiveModel {
+        key: Set(entry.key.clone()),
+        name: Set(entry.name.clone()),
+        category: Set(entry.category.clone()),
+        equipment: Set(entry.equipment),
+        tracking_type: Set(entry.tracking_type),
+        primary_muscles: Set(json!(entry.primary_muscles)),
+        secondary_muscles: Set(json!(entry.secondary_muscles)),
+        aliases: Set(json!(entry.aliases)),
+        ..Default::default()
+    }
+}
+
+fn is_up_to_date(library_exercise: &library_exercises::Model, entry: &LibraryEntry) -> bool {
+    library_exercise.name == entry.name
+        && library_exercise.category == entry.category
+        && library_exercise.equipment == entry.equipment
+        && library_exercise.tracking_type == entry.tracking_type
+        && library_exercise.primary_muscles == json!(entry.primary_muscles)
+        && library_exercise.secondary_muscles == json!(entry.secondary_muscles)
+        && library_exercise.aliases == json!(entry.aliases)
+}
+
+// Called on startup and by the seed command, brings the table in line with the bundled file
+pub async fn seed_exercise_library(database: &DatabaseConnection) -> Result<(), StatusCode> {
+    let mut existing: HashMap<String, library_exercises::Model> = LibraryExercises::find()
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|library_exercise| (library_exercise.key.clone(), library_exercise))
+        .collect();
+
+    let mut library_exercises_to_insert = Vec::new();
+    let mut library_exercises_to_update = Vec::new();
+    for entry in builtin_library() {
+        match existing.remove(&entry.key) {
+            None => library_exercises_to_insert.push(to_active_model(entry)),
+            Some(library_exercise) if !is_up_to_date(&library_exercise, entry) => {
+                let mut library_exercise_to_update = to_active_model(entry);
+                library_exercise_to_update.id = Set(library_exercise.id);
+                library_exercises_to_update.push(library_exercise_to_update);
+            }
+            Some(_) => {}
+        }
+    }
+
+    if library_exercises_to_insert.is_empty() && library_exercises_to_update.is_empty() {
+        return Ok(());
+    }
+    warn!(
+        "seeding exercise library, {} new and {} changed exercises",
+        library_exercises_to_insert.len(),
+        library_exercises_to_update.len()
+    );
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !library_exercises_to_insert.is_empty() {
+        LibraryExercises::insert_many(library_exercises_to_insert)
+            .exec(&txn)
+            .await
+            .map_err(|err| {
+                error!("error seeding the exercise library {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+    for library_exercise in library_exercises_to_update {
+        library_exercise.update(&txn).await.map_err(|err| {
+            error!("error updating the exercise library {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+pub fn library_muscles(library_exercise: &library_exercises::Model) -> Vec<(Muscle, MuscleRole)> {
+    let mut muscles = Vec::new();
+    for (mapped_muscles, role) in [
+        (&library_exercise.primary_muscles, MuscleRole::Primary),
+        (&library_exercise.secondary_muscles, MuscleRole::Secondary),
+    ] {
+        let mapped_muscles: Vec<Muscle> =
+            serde_json::from_value(mapped_muscles.clone()).unwrap_or_default();
+        muscles.extend(mapped_muscles.into_iter().map(|muscle| (muscle, role)));
+    }
+    muscles
+}
+
+fn to_response(
+    library_exercise: library_exercises::Model,
+    exercise: Option<&exercises::Model>,
+) -> ResponseLibraryExercise {
+    ResponseLibraryExercise {
+        id: library_exercise.id,
+        key: library_exercise.key,
+        name: exercise.map_or(library_exercise.name, |exercise| exercise.name.clone()),
+        bodypart: exercise.map_or(library_exercise.category, |exercise| {
+            exercise.bodypart.clone()
+        }),
+        equipment: exercise
+            .and_then(|exercise| exercise.equipment)
+            .unwrap_or(library_exercise.equipment),
+        tracking_type: exercise.map_or(library_exercise.tracking_type, |exercise| {
+            exercise.tracking_type
+        }),
+        primary_muscles: serde_json::from_value(library_exercise.primary_muscles)
+            .unwrap_or_default(),
+        secondary_muscles: serde_json::from_value(library_exercise.secondary_muscles)
+            .unwrap_or_default(),
+        aliases: serde_json::from_value(library_exercise.aliases).unwrap_or_default(),
+        isfavorite: exercise.is_some_and(|exercise| exercise.isfavorite),
+        exercise_id: exercise.map(|exercise| exercise.id),
+    }
+}
+
+// The user's exercises that came from the library, keyed by library id
+async fn find_linked_exercises(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<HashMap<i32, exercises::Model>, StatusCode> {
+    Ok(Exercises::find()
+        .filter(exercises::Column::UserId.eq(user_id))
+        .filter(exercises::Column::LibraryExerciseId.is_not_null())
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter_map(|exercise| {
+            exercise
+                .library_exercise_id
+                .map(|library_exercise_id| (library_exercise_id, exercise))
+        })
+        .collect())
+}
+
+async fn find_library_exercise(
+    database: &DatabaseConnection,
+    library_exercise_id: i32,
+) -> Result<library_exercises::Model, StatusCode> {
+    LibraryExercises::find_by_id(library_exercise_id)
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+pub async fn get_library(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(library_query): Query<LibraryQuery>,
+) -> Result<Json<Vec<ResponseLibraryExercise>>, StatusCode> {
+    let linked_exercises = find_linked_exercises(&database, user.id).await?;
+    let available_equipment = if library_query.available_only {
+        find_available_equipment(&database, user.id, None).await?
+    } else {
+        None
+    };
+
+    let library: Vec<ResponseLibraryExercise> = LibraryExercises::find()
+        .order_by_asc(library_exercises::Column::Name)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|library_exercise| {
+            let exercise = linked_exercises.get(&library_exercise.id);
+            to_response(library_exercise, exercise)
+        })
+        .filter(|library_exercise| {
+            library_query
+                .category
+                .as_ref()
+                .is_none_or(|category| library_exercise.bodypart.eq_ignore_ascii_case(category))
+                && library_query
+                    .equipment
+                    .is_none_or(|equipment| library_exercise.equipment == equipment)
+                && is_available(&available_equipment, Some(library_exercise.equipment))
+                && (!library_query.favorites_only || library_exercise.isfavorite)
+        })
+        .collect();
+
+    warn!(
+        "{} library exercises fetched by user: {}",
+        library.len(),
+        user.username
+    );
+
+    Ok(Json(library))
+}
+
+pub async fn get_one_library_exercise(
+    Extension(user): Extension<Model>,
+    Path(library_exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ResponseLibraryExercise>, StatusCode> {
+    let library_exercise = find_library_exercise(&database, library_exercise_id).await?;
+    let linked_exercises = find_linked_exercises(&database, user.id).await?;
+
+    Ok(Json(to_response(
+        library_exercise,
+        linked_exercises.get(&library_exercise_id),
+    )))
+}
+
+// Favoriting or customizing a library exercise gives the user their own copy, linked back to
+// the library entry. A same-named exercise they made themselves is linked instead of duplicated,
+// since sets are matched to exercises by name.
+pub async fn override_library_exercise(
+    Extension(user): Extension<Model>,
+    Path(library_exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_override): Json<RequestLibraryOverride>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("library exercise customized by user: {}", user.username);
+
+    let library_exercise = find_library_exercise(&database, library_exercise_id).await?;
+    let bodypart = match &request_override.bodypart {
+        Some(bodypart) => {
+            let category_names = find_category_names(&database, user.id).await?;
+            Some(resolve_category(&category_names, bodypart)?)
+        }
+        None => None,
+    };
+    if request_override
+        .name
+        .as_ref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        warn!("empty exercise name");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let linked_exercise = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user.id))
+        .filter(exercises::Column::LibraryExerciseId.eq(library_exercise.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let existing_exercise = match linked_exercise {
+        Some(exercise) => Some(exercise),
+        None => Exercises::find()
+            .filter(exercises::Column::UserId.eq(user.id))
+            .filter(exercises::Column::LibraryExerciseId.is_null())
+            .filter(exercises::Column::Name.eq(library_exercise.name.clone()))
+            .one(&database)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    };
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let is_new = existing_exercise.is_none();
+    let mut exercise = match existing_exercise {
+        Some(exercise) => exercise.into_active_model(),
+        None => exercises::ActiveModel {
+            name: Set(library_exercise.name.clone()),
+            bodypart: Set(library_exercise.category.clone()),
+            isfavorite: Set(false),
+            tracking_type: Set(library_exercise.tracking_type),
+            equipment: Set(Some(library_exercise.equipment)),
+            progression_strategy: Set(String::from(DEFAULT_STRATEGY)),
+            progression_settings: Set(None),
+            user_id: Set(Some(user.id)),
+            ..Default::default()
+        },
+    };
+
+    exercise.library_exercise_id = Set(Some(library_exercise.id));
+    if let Some(name) = request_override.name {
+        exercise.name = Set(name.trim().to_string());
+    }
+    if let Some(bodypart) = bodypart {
+        exercise.bodypart = Set(bodypart);
+    }
+    if let Some(isfavorite) = request_override.isfavorite {
+        exercise.isfavorite = Set(isfavorite);
+    }
+    if let Some(tracking_type) = request_override.tracking_type {
+        exercise.tracking_type = Set(tracking_type);
+    }
+    if let Some(equipment) = request_override.equipment {
+        exercise.equipment = Set(Some(equipment));
+    }
+
+    let exercise = exercise.save(&txn).await.map_err(|err| {
+        error!("error saving the exercise {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let exercise_id = exercise.id.unwrap();
+
+    // A fresh copy starts out with the library's muscle mapping
+    if is_new {
+        let exercise_muscles_to_insert: Vec<exercise_muscles::ActiveModel> =
+            library_muscles(&library_exercise)
+                .into_iter()
+                .map(|(muscle, role)| exercise_muscles::ActiveModel {
+                    exercise_id: Set(exercise_id),
+                    muscle: Set(muscle),
+                    role: Set(role),
+                    ..Default::default()
+                })
+                .collect();
+
+        if !exercise_muscles_to_insert.is_empty() {
+            ExerciseMuscles::insert_many(exercise_muscles_to_insert)
+                .exec(&txn)
+                .await
+                .map_err(|err| {
+                    error!("error saving exercise muscles {}", err);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+        }
+    }
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(exercise_id))
+}
@@ -0,0 +1,127 @@
+use crate::database::sea_orm_active_enums::{SetType, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    exercises, exercises::Entity as Exercises, workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::progression::{
+    find_strategy, ProgressionContext, Session, SessionSet, SuggestionReason,
+};
+use crate::utils::time::{local_date, parse_timezone};
+use crate::utils::units::{convert_weight, resolve_unit};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use log::{error, warn};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+// Enough sessions to spot a stall without dragging in stale history
+const HISTORY_SESSIONS: usize = 10;
+
+#[derive(Deserialize, Debug)]
+pub struct NextSessionQuery {
+    pub unit: Option<WeightUnit>,
+    // Try a different strategy than the one configured on the exercise
+    pub strategy: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseNextSession {
+    pub exercise_id: i32,
+    pub exercise_name: String,
+    pub strategy: String,
+    pub weight: Option<f64>,
+    pub reps: i32,
+    pub sets: usize,
+    pub unit: WeightUnit,
+    pub reason: SuggestionReason,
+    pub last_session: Option<NaiveDate>,
+}
+
+pub async fn get_next_session(
+    Extension(user): Extension<Model>,
+    Path(exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(next_session_query): Query<NextSessionQuery>,
+) -> Result<Json<ResponseNextSession>, StatusCode> {
+    warn!("next session suggestion fetched by user: {}", user.username);
+    let unit = resolve_unit(next_session_query.unit, &user);
+
+    let exercise = Exercises::find_by_id(exercise_id)
+        .filter(exercises::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let strategy_name = next_session_query
+        .strategy
+        .unwrap_or(exercise.progression_strategy.clone());
+    let Some(strategy) = find_strategy(&strategy_name) else {
+        warn!("unknown progression strategy {}", strategy_name);
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    // Settings belong to the configured strategy, an override runs on its defaults
+    let settings = (strategy_name == exercise.progression_strategy)
+        .then_some(exercise.progression_settings.as_ref())
+        .flatten();
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+
+    // Warm ups and drop sets aren't the work being progressed
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::ExerciseName.eq(exercise.name.clone()))
+        .filter(workout_sets::Column::SetType.is_not_in([SetType::WarmUp, SetType::DropSet]))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut sessions: Vec<Session> = Vec::new();
+    for workout_set in workout_sets {
+        let date = local_date(&workout_set.date, timezone);
+        let session_set = SessionSet {
+            reps: workout_set.reps,
+            weight: convert_weight(workout_set.weight, workout_set.weight_unit, unit),
+            rpe: workout_set.rpe,
+        };
+
+        match sessions.last_mut() {
+            Some(session) if session.date == date => session.sets.push(session_set),
+            _ => sessions.push(Session {
+                date,
+                sets: vec![session_set],
+            }),
+        }
+    }
+    let recent_sessions = &sessions[sessions.len().saturating_sub(HISTORY_SESSIONS)..];
+
+    let suggestion = strategy
+        .suggest(&ProgressionContext {
+            sessions: recent_sessions,
+            unit,
+            settings,
+        })
+        .map_err(|err| {
+            error!("error running progression strategy {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(ResponseNextSession {
+        exercise_id: exercise.id,
+        exercise_name: exercise.name,
+        strategy: strategy_name,
+        weight: suggestion.weight,
+        reps: suggestion.reps,
+        sets: suggestion.sets,
+        unit,
+        reason: suggestion.reason,
+        last_session: recent_sessions.last().map(|session| session.date),
+    }))
+}
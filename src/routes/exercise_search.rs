@@ -0,0 +1,188 @@
+use crate::database::users::Model;
+use crate::database::{
+    exercises, exercises::Entity as Exercises, library_exercises::Entity as LibraryExercises,
+    workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::utils::search::{match_exercise, normalize, MatchKind, SearchMatch};
+use axum::{extract::Query, http::StatusCode, Extension, Json};
+use log::warn;
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QuerySelect};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Deserialize, Debug)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseSearchResult {
+    pub name: String,
+    pub bodypart: String,
+    pub exercise_id: Option<i32>,
+    pub library_exercise_id: Option<i32>,
+    pub match_kind: MatchKind,
+    pub usage_count: i64,
+}
+
+struct Candidate {
+    name: String,
+    bodypart: String,
+    exercise_id: Option<i32>,
+    library_exercise_id: Option<i32>,
+    aliases: Vec<String>,
+}
+
+// Number of sets logged per exercise, keyed by normalized name
+async fn find_usage_counts(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<HashMap<String, i64>, StatusCode> {
+    let usage_counts: Vec<(String, i64)> = WorkoutSets::find()
+        .select_only()
+        .column(workout_sets::Column::ExerciseName)
+        .column_as(Expr::col(workout_sets::Column::Id).count(), "usage_count")
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .group_by(workout_sets::Column::ExerciseName)
+        .into_tuple()
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut usage = HashMap::new();
+    for (exercise_name, usage_count) in usage_counts {
+        *usage.entry(normalize(&exercise_name)).or_default() += usage_count;
+    }
+    Ok(usage)
+}
+
+// The user's exercises come first, library exercises only when the user has no copy of them
+async fn find_candidates(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Vec<Candidate>, StatusCode> {
+    let library = LibraryExercises::find()
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let user_exercises = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user_id))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let library_aliases: HashMap<i32, Vec<String>> = library
+        .iter()
+        .map(|library_exercise| {
+            let aliases: Vec<String> =
+                serde_json::from_value(library_exercise.aliases.clone()).unwrap_or_default();
+            (
+                library_exercise.id,
+                aliases.iter().map(|alias| normalize(alias)).collect(),
+            )
+        })
+        .collect();
+    let library_ids_by_name: HashMap<String, i32> = library
+        .iter()
+        .map(|library_exercise| (normalize(&library_exercise.name), library_exercise.id))
+        .collect();
+
+    let mut candidates = Vec::new();
+    let mut covered_library_ids = HashSet::new();
+    for exercise in user_exercises {
+        // Exercises the user made themselves still pick up aliases from a same-named library one
+        let library_exercise_id = exercise
+            .library_exercise_id
+            .or_else(|| library_ids_by_name.get(&normalize(&exercise.name)).copied());
+        if let Some(library_exercise_id) = library_exercise_id {
+            covered_library_ids.insert(library_exercise_id);
+        }
+
+        candidates.push(Candidate {
+            name: exercise.name,
+            bodypart: exercise.bodypart,
+            exercise_id: Some(exercise.id),
+            library_exercise_id,
+            aliases: library_exercise_id
+                .and_then(|library_exercise_id| library_aliases.get(&library_exercise_id))
+                .cloned()
+                .unwrap_or_default(),
+        });
+    }
+
+    for library_exercise in library {
+        if covered_library_ids.contains(&library_exercise.id) {
+            continue;
+        }
+        candidates.push(Candidate {
+            aliases: library_aliases
+                .get(&library_exercise.id)
+                .cloned()
+                .unwrap_or_default(),
+            name: library_exercise.name,
+            bodypart: library_exercise.category,
+            exercise_id: None,
+            library_exercise_id: Some(library_exercise.id),
+        });
+    }
+
+    Ok(candidates)
+}
+
+// Ranked by how well the name matched, then by how often the user logs the exercise
+pub async fn search_exercises(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(search_query): Query<SearchQuery>,
+) -> Result<Json<Vec<ResponseSearchResult>>, StatusCode> {
+    let query = normalize(&search_query.q);
+    let limit = search_query.limit.unwrap_or(20);
+    if query.is_empty() || !(1..=100).contains(&limit) {
+        warn!("invalid exercise search {:?}", search_query);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let usage_counts = find_usage_counts(&database, user.id).await?;
+    let mut results: Vec<(SearchMatch, ResponseSearchResult)> = find_candidates(&database, user.id)
+        .await?
+        .into_iter()
+        .filter_map(|candidate| {
+            let normalized_name = normalize(&candidate.name);
+            let search_match = match_exercise(&query, &normalized_name, &candidate.aliases)?;
+            Some((
+                search_match,
+                ResponseSearchResult {
+                    usage_count: usage_counts.get(&normalized_name).copied().unwrap_or(0),
+                    name: candidate.name,
+                    bodypart: candidate.bodypart,
+                    exercise_id: candidate.exercise_id,
+                    library_exercise_id: candidate.library_exercise_id,
+                    match_kind: search_match.kind,
+                },
+            ))
+        })
+        .collect();
+
+    results.sort_by_key(|(search_match, result)| {
+        (
+            search_match.kind,
+            Reverse(result.usage_count),
+            search_match.distance,
+            result.name.clone(),
+        )
+    });
+    results.truncate(limit);
+
+    warn!(
+        "exercise search returned {} results for user: {}",
+        results.len(),
+        user.username
+    );
+
+    Ok(Json(
+        results.into_iter().map(|(_, result)| result).collect(),
+    ))
+}
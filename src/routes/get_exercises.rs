@@ -1,20 +1,40 @@
 use crate::database::{
     exercises::{self, Entity as Exercises},
-    sea_orm_active_enums::Bodypart,
+    sea_orm_active_enums::{Equipment, TrackingType},
     users::Model,
 };
-use axum::{extract::Path, http::StatusCode, response::IntoResponse, Extension, Json};
+use crate::routes::equipment::{find_available_equipment, is_available};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
 use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
 use sea_orm::{DatabaseConnection, EntityTrait, IntoActiveModel};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Deserialize, Debug)]
+pub struct ExercisesQuery {
+    pub equipment: Option<Equipment>,
+    // Only exercises doable with the active equipment profile
+    #[serde(default)]
+    pub available_only: bool,
+}
 
 #[derive(Serialize)]
 pub struct ResponseExercise {
     id: i32,
     name: String,
-    bodypart: Bodypart,
+    bodypart: String,
     isfavorite: bool,
+    tracking_type: TrackingType,
+    equipment: Option<Equipment>,
+    progression_strategy: String,
+    progression_settings: Option<Value>,
+    library_exercise_id: Option<i32>,
     user_id: Option<i32>,
 }
 
@@ -37,6 +57,11 @@ pub async fn get_one_exercise(
             name: exercise.name,
             bodypart: exercise.bodypart,
             isfavorite: exercise.isfavorite,
+            tracking_type: exercise.tracking_type,
+            equipment: exercise.equipment,
+            progression_strategy: exercise.progression_strategy,
+            progression_settings: exercise.progression_settings,
+            library_exercise_id: exercise.library_exercise_id,
             user_id: exercise.user_id,
         }));
     }
@@ -47,7 +72,13 @@ pub async fn get_one_exercise(
 pub async fn get_all_exercises(
     Extension(user): Extension<Model>,
     Extension(database): Extension<DatabaseConnection>,
+    Query(exercises_query): Query<ExercisesQuery>,
 ) -> Result<Json<Vec<ResponseExercise>>, StatusCode> {
+    let available_equipment = if exercises_query.available_only {
+        find_available_equipment(&database, user.id, None).await?
+    } else {
+        None
+    };
     let user = user.into_active_model();
     let exercises = Exercises::find()
         .filter(exercises::Column::UserId.eq(user.id.unwrap()))
@@ -55,11 +86,22 @@ pub async fn get_all_exercises(
         .await
         .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?
         .into_iter()
+        .filter(|db_exercise| {
+            exercises_query
+                .equipment
+                .is_none_or(|equipment| db_exercise.equipment == Some(equipment))
+                && is_available(&available_equipment, db_exercise.equipment)
+        })
         .map(|db_exercise| ResponseExercise {
             id: db_exercise.id,
             name: db_exercise.name,
             bodypart: db_exercise.bodypart,
             isfavorite: db_exercise.isfavorite,
+            tracking_type: db_exercise.tracking_type,
+            equipment: db_exercise.equipment,
+            progression_strategy: db_exercise.progression_strategy,
+            progression_settings: db_exercise.progression_settings,
+            library_exercise_id: db_exercise.library_exercise_id,
             user_id: db_exercise.user_id,
         })
         .collect();
@@ -2,8 +2,17 @@ use crate::database::users;
 use crate::database::users::Entity as Users;
 use crate::database::users::Model;
 use crate::database::workout_sets;
-use crate::database::{sea_orm_active_enums::Bodypart, workout_sets::Entity as WorkoutSets};
-use axum::{extract::Path, http::StatusCode, Extension, Json};
+use crate::database::{
+    sea_orm_active_enums::{SetGroupType, SetType, WeightUnit},
+    workout_sets::Entity as WorkoutSets,
+};
+use crate::utils::set_filter::SetTypeFilter;
+use crate::utils::units::{convert_weight, resolve_unit, UnitQuery};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
 use log::warn;
 use sea_orm::ColumnTrait;
 use sea_orm::QueryFilter;
@@ -15,19 +24,69 @@ pub struct ResponseWorkoutSet {
     pub id: i32,
     pub date: DateTimeWithTimeZone,
     pub exercise_name: String,
-    pub category: Bodypart,
+    pub category: String,
     pub reps: i32,
     pub weight: f64,
+    pub unit: WeightUnit,
+    pub set_type: SetType,
+    pub rpe: Option<f64>,
+    pub rir: Option<i32>,
+    pub duration_seconds: Option<i32>,
+    pub distance_meters: Option<f64>,
+    pub pace_seconds_per_km: Option<f64>,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
     pub comment: Option<String>,
+    pub order_index: Option<i32>,
+    pub group_id: Option<String>,
+    pub group_type: Option<SetGroupType>,
+    pub rest_seconds: Option<i32>,
+    pub time_under_tension_seconds: Option<i32>,
     pub user_id: Option<i32>,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct SetsQuery {
+    pub unit: Option<WeightUnit>,
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+}
+
+pub fn to_response(workout_set: workout_sets::Model, unit: WeightUnit) -> ResponseWorkoutSet {
+    ResponseWorkoutSet {
+        id: workout_set.id,
+        date: workout_set.date,
+        exercise_name: workout_set.exercise_name,
+        category: workout_set.category,
+        reps: workout_set.reps,
+        weight: convert_weight(workout_set.weight, workout_set.weight_unit, unit),
+        unit,
+        set_type: workout_set.set_type,
+        rpe: workout_set.rpe,
+        rir: workout_set.rir,
+        duration_seconds: workout_set.duration_seconds,
+        distance_meters: workout_set.distance_meters,
+        pace_seconds_per_km: workout_set.pace_seconds_per_km,
+        avg_heart_rate: workout_set.avg_heart_rate,
+        max_heart_rate: workout_set.max_heart_rate,
+        comment: workout_set.comment,
+        order_index: workout_set.order_index,
+        group_id: workout_set.group_id,
+        group_type: workout_set.group_type,
+        rest_seconds: workout_set.rest_seconds,
+        time_under_tension_seconds: workout_set.time_under_tension_seconds,
+        user_id: workout_set.user_id,
+    }
+}
+
 pub async fn get_one_workout_set(
     Extension(user): Extension<Model>,
     Path(set_id): Path<i32>,
     Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
 ) -> Result<Json<ResponseWorkoutSet>, StatusCode> {
     warn!("set fetched by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
     let user = user.into_active_model();
 
     let workout_set = WorkoutSets::find_by_id(set_id)
@@ -37,16 +96,7 @@ pub async fn get_one_workout_set(
         .unwrap();
 
     if let Some(workout_set) = workout_set {
-        return Ok(Json(ResponseWorkoutSet {
-            id: workout_set.id,
-            date: workout_set.date,
-            exercise_name: workout_set.exercise_name,
-            category: workout_set.category,
-            reps: workout_set.reps,
-            weight: workout_set.weight,
-            comment: workout_set.comment,
-            user_id: workout_set.user_id,
-        }));
+        return Ok(Json(to_response(workout_set, unit)));
     }
 
     Err(StatusCode::NOT_FOUND)
@@ -55,7 +105,14 @@ pub async fn get_one_workout_set(
 pub async fn get_all_workout_sets(
     Extension(user): Extension<Model>,
     Extension(database): Extension<DatabaseConnection>,
+    Query(sets_query): Query<SetsQuery>,
 ) -> Result<Json<Vec<ResponseWorkoutSet>>, StatusCode> {
+    let unit = resolve_unit(sets_query.unit, &user);
+    let set_type_filter = SetTypeFilter::from_query(
+        sets_query.include_types.as_deref(),
+        sets_query.exclude_types.as_deref(),
+        &[],
+    )?;
     let user = user.into_active_model();
 
     let workout_sets: Vec<ResponseWorkoutSet> = WorkoutSets::find()
@@ -64,16 +121,8 @@ pub async fn get_all_workout_sets(
         .await
         .map_err(|_error| StatusCode::INTERNAL_SERVER_ERROR)?
         .into_iter()
-        .map(|db_workout_set| ResponseWorkoutSet {
-            id: db_workout_set.id,
-            date: db_workout_set.date,
-            exercise_name: db_workout_set.exercise_name,
-            category: db_workout_set.category,
-            reps: db_workout_set.reps,
-            weight: db_workout_set.weight,
-            comment: db_workout_set.comment,
-            user_id: db_workout_set.user_id,
-        })
+        .filter(|db_workout_set| set_type_filter.includes(db_workout_set.set_type))
+        .map(|db_workout_set| to_response(db_workout_set, unit))
         .collect();
 
     warn!("{} sets fetched by user: {}", workout_sets.len(), user.username.unwrap());
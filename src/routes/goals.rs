@@ -0,0 +1,609 @@
+use crate::database::sea_orm_active_enums::{GoalKind, MeasurementKind, SetType, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    goals, goals::Entity as Goals, user_profiles, workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::measurements::{find_measurements, is_valid_measurement, MeasurementQuery};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::goals::{is_reached, progress_percent, project_completion, GoalPoint};
+use crate::utils::strength::estimated_one_rep_max;
+use crate::utils::time::{local_date, parse_timezone, week_start_date};
+use crate::utils::units::{convert_measurement, convert_weight, resolve_unit};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct RequestGoal {
+    pub kind: GoalKind,
+    pub title: String,
+    // Required for strength goals
+    pub exercise_name: Option<String>,
+    // Required for measurement goals
+    pub measurement_kind: Option<MeasurementKind>,
+    // Estimated 1RM for strength goals, training days per week for frequency goals
+    pub target_value: f64,
+    pub unit: Option<WeightUnit>,
+    pub deadline: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestGoalUpdate {
+    pub title: String,
+    pub target_value: f64,
+    pub unit: Option<WeightUnit>,
+    pub deadline: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GoalsQuery {
+    pub unit: Option<WeightUnit>,
+    #[serde(default)]
+    pub include_completed: bool,
+}
+
+#[derive(Serialize)]
+pub struct ResponseGoal {
+    pub id: i32,
+    pub kind: GoalKind,
+    pub title: String,
+    pub exercise_name: Option<String>,
+    pub measurement_kind: Option<MeasurementKind>,
+    pub unit: WeightUnit,
+    pub target_value: f64,
+    pub start_value: Option<f64>,
+    pub current_value: Option<f64>,
+    pub progress_percent: f64,
+    pub deadline: Option<NaiveDate>,
+    pub projected_completion: Option<NaiveDate>,
+    // Whether the projection lands before the deadline, unknown without either of them
+    pub on_track: Option<bool>,
+    pub created_at: DateTimeWithTimeZone,
+    pub completed_at: Option<DateTimeWithTimeZone>,
+}
+
+// Targets are stored in kg and cm so they survive the user switching units
+fn to_stored(
+    kind: GoalKind,
+    measurement_kind: Option<MeasurementKind>,
+    value: f64,
+    unit: WeightUnit,
+) -> f64 {
+    match (kind, measurement_kind) {
+        (GoalKind::Strength, _) => convert_weight(value, unit, WeightUnit::Kg),
+        (GoalKind::Measurement, Some(measurement_kind)) => {
+            convert_measurement(measurement_kind, value, unit, WeightUnit::Kg)
+        }
+        _ => value,
+    }
+}
+
+fn from_stored(goal: &goals::Model, value: f64, unit: WeightUnit) -> f64 {
+    match (goal.kind, goal.measurement_kind) {
+        (GoalKind::Strength, _) => convert_weight(value, WeightUnit::Kg, unit),
+        (GoalKind::Measurement, Some(measurement_kind)) => {
+            convert_measurement(measurement_kind, value, WeightUnit::Kg, unit)
+        }
+        _ => value,
+    }
+}
+
+fn validate_title(title: &str) -> Result<String, StatusCode> {
+    let title = title.trim().to_string();
+    if title.is_empty() || title.len() > 100 {
+        warn!("invalid goal title {}", title);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(title)
+}
+
+fn validate_target(
+    kind: GoalKind,
+    measurement_kind: Option<MeasurementKind>,
+    target_value: f64,
+) -> Result<(), StatusCode> {
+    let is_valid = match (kind, measurement_kind) {
+        (GoalKind::Strength, _) => target_value > 0.0 && target_value <= 1000.0,
+        (GoalKind::Frequency, _) => (1.0..=7.0).contains(&target_value),
+        (GoalKind::Measurement, Some(measurement_kind)) => {
+            is_valid_measurement(measurement_kind, target_value)
+        }
+        (GoalKind::Measurement, None) => false,
+    };
+    if !is_valid {
+        warn!("invalid goal target {:?} {}", kind, target_value);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(())
+}
+
+// Strength only goes up, a measurement target can sit on either side of the starting value
+fn is_already_reached(kind: GoalKind, start_value: Option<f64>, target_value: f64) -> bool {
+    match kind {
+        GoalKind::Strength => start_value.is_some_and(|start_value| start_value >= target_value),
+        _ => start_value == Some(target_value),
+    }
+}
+
+async fn find_goal(
+    database: &DatabaseConnection,
+    user_id: i32,
+    goal_id: i32,
+) -> Result<goals::Model, StatusCode> {
+    Goals::find_by_id(goal_id)
+        .filter(goals::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Best estimated 1RM per training day, oldest first and in kg
+async fn find_strength_points(
+    database: &DatabaseConnection,
+    user_id: i32,
+    exercise_name: &str,
+) -> Result<Vec<GoalPoint>, StatusCode> {
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .filter(workout_sets::Column::ExerciseName.eq(exercise_name))
+        .filter(workout_sets::Column::SetType.ne(SetType::WarmUp))
+        .filter(workout_sets::Column::Reps.gt(0))
+        .filter(workout_sets::Column::Weight.gt(0.0))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut points: Vec<GoalPoint> = Vec::new();
+    for workout_set in workout_sets {
+        let weight = convert_weight(workout_set.weight, workout_set.weight_unit, WeightUnit::Kg);
+        let one_rep_max = estimated_one_rep_max(weight, workout_set.reps);
+        match points.last_mut() {
+            Some(last) if last.date.date_naive() == workout_set.date.date_naive() => {
+                last.value = last.value.max(one_rep_max);
+            }
+            _ => points.push(GoalPoint {
+                date: workout_set.date,
+                value: one_rep_max,
+            }),
+        }
+    }
+    Ok(points)
+}
+
+// Every measurement of the kind, oldest first and in kg or cm
+async fn find_measurement_points(
+    database: &DatabaseConnection,
+    user_id: i32,
+    measurement_kind: MeasurementKind,
+) -> Result<Vec<GoalPoint>, StatusCode> {
+    let query = MeasurementQuery {
+        kind: Some(measurement_kind),
+        unit: None,
+        from: None,
+        to: None,
+    };
+
+    Ok(find_measurements(database, user_id, &query)
+        .await?
+        .into_iter()
+        .map(|measurement| GoalPoint {
+            date: measurement.date,
+            value: convert_measurement(
+                measurement_kind,
+                measurement.value,
+                measurement.unit,
+                WeightUnit::Kg,
+            ),
+        })
+        .collect())
+}
+
+// Days trained so far in the user's current week, a point on the first set of every local day
+async fn find_training_day_points(
+    database: &DatabaseConnection,
+    profile: &user_profiles::Model,
+) -> Result<Vec<GoalPoint>, StatusCode> {
+    let timezone = parse_timezone(&profile.timezone);
+    let now: DateTimeWithTimeZone = Utc::now().into();
+    let week_start = week_start_date(local_date(&now, timezone), profile.week_start);
+
+    // A day of slack covers every timezone, the exact cut happens on local dates
+    let midnight = Utc.from_utc_datetime(&week_start.and_hms_opt(0, 0, 0).unwrap());
+    let since: DateTimeWithTimeZone = (midnight - Duration::days(1)).into();
+
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(profile.user_id))
+        .filter(workout_sets::Column::Date.gte(since))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut points: Vec<GoalPoint> = Vec::new();
+    let mut last_day = None;
+    for workout_set in workout_sets {
+        let day = local_date(&workout_set.date, timezone);
+        if day < week_start || last_day == Some(day) {
+            continue;
+        }
+        last_day = Some(day);
+        points.push(GoalPoint {
+            date: workout_set.date,
+            value: (points.len() + 1) as f64,
+        });
+    }
+    Ok(points)
+}
+
+// The history a strength or measurement goal is tracked against, frequency goals have none
+async fn find_goal_points(
+    database: &DatabaseConnection,
+    user_id: i32,
+    kind: GoalKind,
+    exercise_name: Option<&str>,
+    measurement_kind: Option<MeasurementKind>,
+) -> Result<Vec<GoalPoint>, StatusCode> {
+    match (kind, exercise_name, measurement_kind) {
+        (GoalKind::Strength, Some(exercise_name), _) => {
+            find_strength_points(database, user_id, exercise_name).await
+        }
+        (GoalKind::Measurement, _, Some(measurement_kind)) => {
+            find_measurement_points(database, user_id, measurement_kind).await
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+// Strength goals count the best lift so far, measurement goals the latest reading
+fn current_value(kind: GoalKind, points: &[GoalPoint]) -> Option<f64> {
+    match kind {
+        GoalKind::Strength => points.iter().map(|point| point.value).reduce(f64::max),
+        _ => points.last().map(|point| point.value),
+    }
+}
+
+// The goals a written set can move
+pub const SET_GOAL_KINDS: [GoalKind; 2] = [GoalKind::Strength, GoalKind::Frequency];
+
+struct GoalProgress {
+    current: Option<f64>,
+    start: f64,
+    projected_completion: Option<NaiveDate>,
+    // When the first point crossed the target
+    reached_at: Option<DateTimeWithTimeZone>,
+}
+
+fn first_reached(points: &[GoalPoint], start: f64, target: f64) -> Option<DateTimeWithTimeZone> {
+    points
+        .iter()
+        .find(|point| is_reached(start, point.value, target))
+        .map(|point| point.date)
+}
+
+async fn measure_goal(
+    database: &DatabaseConnection,
+    profile: &user_profiles::Model,
+    goal: &goals::Model,
+) -> Result<GoalProgress, StatusCode> {
+    // Frequency goals complete the first week the user trains often enough
+    if goal.kind == GoalKind::Frequency {
+        let points = find_training_day_points(database, profile).await?;
+        return Ok(GoalProgress {
+            current: Some(points.last().map(|point| point.value).unwrap_or(0.0)),
+            start: 0.0,
+            projected_completion: None,
+            reached_at: first_reached(&points, 0.0, goal.target_value),
+        });
+    }
+
+    let points = find_goal_points(
+        database,
+        profile.user_id,
+        goal.kind,
+        goal.exercise_name.as_deref(),
+        goal.measurement_kind,
+    )
+    .await?;
+    let since_created: Vec<GoalPoint> = points
+        .iter()
+        .filter(|point| point.date >= goal.created_at)
+        .cloned()
+        .collect();
+    // Without a starting value strength counts from zero and measurements from the
+    // first reading after the goal was set
+    let start = match (goal.start_value, goal.kind) {
+        (Some(start_value), _) => start_value,
+        (None, GoalKind::Measurement) => since_created
+            .first()
+            .map(|point| point.value)
+            .unwrap_or(0.0),
+        (None, _) => 0.0,
+    };
+
+    Ok(GoalProgress {
+        current: current_value(goal.kind, &points),
+        start,
+        projected_completion: project_completion(&since_created, goal.target_value),
+        reached_at: first_reached(&since_created, start, goal.target_value),
+    })
+}
+
+async fn evaluate_goal(
+    database: &DatabaseConnection,
+    profile: &user_profiles::Model,
+    goal: goals::Model,
+    unit: WeightUnit,
+) -> Result<ResponseGoal, StatusCode> {
+    let GoalProgress {
+        current,
+        start,
+        projected_completion,
+        ..
+    } = measure_goal(database, profile, &goal).await?;
+
+    let projected_completion = projected_completion.filter(|_| goal.completed_at.is_none());
+    let on_track = match (projected_completion, goal.deadline) {
+        (Some(projected_completion), Some(deadline)) => Some(projected_completion <= deadline),
+        _ => None,
+    };
+    let progress = match (goal.completed_at, current) {
+        (Some(_), _) => 100.0,
+        (None, Some(current)) => progress_percent(start, current, goal.target_value),
+        (None, None) => 0.0,
+    };
+
+    Ok(ResponseGoal {
+        id: goal.id,
+        kind: goal.kind,
+        title: goal.title.clone(),
+        exercise_name: goal.exercise_name.clone(),
+        measurement_kind: goal.measurement_kind,
+        unit,
+        target_value: from_stored(&goal, goal.target_value, unit),
+        start_value: goal
+            .start_value
+            .map(|start_value| from_stored(&goal, start_value, unit)),
+        current_value: current.map(|current| from_stored(&goal, current, unit)),
+        progress_percent: progress,
+        deadline: goal.deadline,
+        projected_completion,
+        on_track,
+        created_at: goal.created_at,
+        completed_at: goal.completed_at,
+    })
+}
+
+// Stamps the user's open goals of the given kinds as completed on the date their target was first
+// reached. Runs after writing the sets or measurements the goals track so fetching stays read-only.
+pub async fn complete_reached_goals(
+    database: &DatabaseConnection,
+    user_id: i32,
+    kinds: &[GoalKind],
+) -> Result<(), StatusCode> {
+    let open_goals = Goals::find()
+        .filter(goals::Column::UserId.eq(user_id))
+        .filter(goals::Column::CompletedAt.is_null())
+        .filter(goals::Column::Kind.is_in(kinds.iter().copied()))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if open_goals.is_empty() {
+        return Ok(());
+    }
+
+    let profile = find_profile_or_default(database, user_id).await?;
+    for goal in open_goals {
+        if let Some(reached_at) = measure_goal(database, &profile, &goal).await?.reached_at {
+            let mut completed_goal = goal.into_active_model();
+            completed_goal.completed_at = Set(Some(reached_at));
+            completed_goal.update(database).await.map_err(|err| {
+                error!("error saving the goal {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn get_all_goals(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(goals_query): Query<GoalsQuery>,
+) -> Result<Json<Vec<ResponseGoal>>, StatusCode> {
+    warn!("goals fetched by user: {}", user.username);
+    let unit = resolve_unit(goals_query.unit, &user);
+    let profile = find_profile_or_default(&database, user.id).await?;
+
+    let mut query = Goals::find().filter(goals::Column::UserId.eq(user.id));
+    if !goals_query.include_completed {
+        query = query.filter(goals::Column::CompletedAt.is_null());
+    }
+    let goals = query
+        .order_by_asc(goals::Column::CreatedAt)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut response_goals = Vec::new();
+    for goal in goals {
+        response_goals.push(evaluate_goal(&database, &profile, goal, unit).await?);
+    }
+
+    Ok(Json(response_goals))
+}
+
+pub async fn get_one_goal(
+    Extension(user): Extension<Model>,
+    Path(goal_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(goals_query): Query<GoalsQuery>,
+) -> Result<Json<ResponseGoal>, StatusCode> {
+    warn!("goal fetched by user: {}", user.username);
+    let unit = resolve_unit(goals_query.unit, &user);
+    let profile = find_profile_or_default(&database, user.id).await?;
+
+    let goal = find_goal(&database, user.id, goal_id).await?;
+    Ok(Json(evaluate_goal(&database, &profile, goal, unit).await?))
+}
+
+pub async fn create_goal(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_goal): Json<RequestGoal>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("goal created by user: {}", user.username);
+
+    let title = validate_title(&request_goal.title)?;
+    let exercise_name = match request_goal.kind {
+        GoalKind::Strength => {
+            let exercise_name = request_goal
+                .exercise_name
+                .as_deref()
+                .map(str::trim)
+                .filter(|exercise_name| !exercise_name.is_empty())
+                .ok_or(StatusCode::BAD_REQUEST)?;
+            Some(exercise_name.to_string())
+        }
+        _ => None,
+    };
+    let measurement_kind = match request_goal.kind {
+        GoalKind::Measurement => Some(
+            request_goal
+                .measurement_kind
+                .ok_or(StatusCode::BAD_REQUEST)?,
+        ),
+        _ => None,
+    };
+    validate_target(
+        request_goal.kind,
+        measurement_kind,
+        request_goal.target_value,
+    )?;
+
+    let unit = request_goal.unit.unwrap_or(user.preferred_unit);
+    let target_value = to_stored(
+        request_goal.kind,
+        measurement_kind,
+        request_goal.target_value,
+        unit,
+    );
+
+    // Where the user stands today, progress is measured from here
+    let points = find_goal_points(
+        &database,
+        user.id,
+        request_goal.kind,
+        exercise_name.as_deref(),
+        measurement_kind,
+    )
+    .await?;
+    let start_value = match request_goal.kind {
+        GoalKind::Frequency => Some(0.0),
+        kind => current_value(kind, &points),
+    };
+    if is_already_reached(request_goal.kind, start_value, target_value) {
+        warn!("goal target {} is already reached", target_value);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let goal = goals::ActiveModel {
+        kind: Set(request_goal.kind),
+        title: Set(title),
+        exercise_name: Set(exercise_name),
+        measurement_kind: Set(measurement_kind),
+        target_value: Set(target_value),
+        start_value: Set(start_value),
+        deadline: Set(request_goal.deadline),
+        created_at: Set(Utc::now().into()),
+        completed_at: Set(None),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving the goal {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(goal.id))
+}
+
+// A new target reopens a completed goal and is checked against the history right away
+pub async fn update_goal(
+    Extension(user): Extension<Model>,
+    Path(goal_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_goal): Json<RequestGoalUpdate>,
+) -> Result<(), StatusCode> {
+    warn!("goal updated by user: {}", user.username);
+
+    let title = validate_title(&request_goal.title)?;
+    let goal = find_goal(&database, user.id, goal_id).await?;
+    validate_target(goal.kind, goal.measurement_kind, request_goal.target_value)?;
+
+    let unit = request_goal.unit.unwrap_or(user.preferred_unit);
+    let target_value = to_stored(
+        goal.kind,
+        goal.measurement_kind,
+        request_goal.target_value,
+        unit,
+    );
+    let is_new_target = (target_value - goal.target_value).abs() > f64::EPSILON;
+    if is_new_target && is_already_reached(goal.kind, goal.start_value, target_value) {
+        warn!("goal target {} is already reached", target_value);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut goal = goal.into_active_model();
+    goal.title = Set(title);
+    goal.deadline = Set(request_goal.deadline);
+    if is_new_target {
+        goal.target_value = Set(target_value);
+        goal.completed_at = Set(None);
+    }
+    let goal = goal.update(&database).await.map_err(|err| {
+        error!("error saving the goal {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if is_new_target {
+        complete_reached_goals(&database, user.id, &[goal.kind]).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_goal(
+    Extension(user): Extension<Model>,
+    Path(goal_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("goal deleted by user: {}", user.username);
+
+    let result = Goals::delete_many()
+        .filter(goals::Column::UserId.eq(user.id))
+        .filter(goals::Column::Id.eq(goal_id))
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,339 @@
+use crate::database::sea_orm_active_enums::{GoalKind, MeasurementKind, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{measurements, measurements::Entity as Measurements};
+use crate::routes::goals::complete_reached_goals;
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::time::{local_date, parse_timezone};
+use crate::utils::units::{convert_measurement, resolve_unit};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::NaiveDate;
+use log::{error, warn};
+use sea_orm::{prelude::DateTimeWithTimeZone, DatabaseConnection};
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct RequestMeasurement {
+    pub date: DateTimeWithTimeZone,
+    pub kind: MeasurementKind,
+    pub value: f64,
+    pub unit: Option<WeightUnit>,
+    pub comment: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MeasurementQuery {
+    pub kind: Option<MeasurementKind>,
+    pub unit: Option<WeightUnit>,
+    pub from: Option<DateTimeWithTimeZone>,
+    pub to: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseMeasurement {
+    pub id: i32,
+    pub date: DateTimeWithTimeZone,
+    pub kind: MeasurementKind,
+    pub value: f64,
+    pub unit: WeightUnit,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SeriesPoint {
+    pub date: NaiveDate,
+    pub value: f64,
+}
+
+#[derive(Serialize)]
+pub struct ResponseSeries {
+    pub kind: MeasurementKind,
+    pub unit: WeightUnit,
+    pub points: Vec<SeriesPoint>,
+}
+
+pub fn is_valid_measurement(kind: MeasurementKind, value: f64) -> bool {
+    match kind {
+        MeasurementKind::BodyFat => (0.0..=100.0).contains(&value),
+        _ => value > 0.0,
+    }
+}
+
+fn to_response(measurement: measurements::Model, unit: WeightUnit) -> ResponseMeasurement {
+    ResponseMeasurement {
+        id: measurement.id,
+        date: measurement.date,
+        kind: measurement.kind,
+        value: convert_measurement(measurement.kind, measurement.value, measurement.unit, unit),
+        unit,
+        comment: measurement.comment,
+    }
+}
+
+// Shared by the list and series endpoints, ordered oldest first
+pub async fn find_measurements(
+    database: &DatabaseConnection,
+    user_id: i32,
+    query: &MeasurementQuery,
+) -> Result<Vec<measurements::Model>, StatusCode> {
+    let mut select = Measurements::find().filter(measurements::Column::UserId.eq(user_id));
+
+    if let Some(kind) = query.kind {
+        select = select.filter(measurements::Column::Kind.eq(kind));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(measurements::Column::Date.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(measurements::Column::Date.lte(to));
+    }
+
+    select
+        .order_by_asc(measurements::Column::Date)
+        .all(database)
+        .await
+        .map_err(|err| {
+            error!("error finding measurements {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+pub async fn create_measurement(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_measurement): Json<RequestMeasurement>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("measurement created by user: {}", user.username);
+
+    if !is_valid_measurement(request_measurement.kind, request_measurement.value) {
+        warn!("invalid measurement value {}", request_measurement.value);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let new_measurement = measurements::ActiveModel {
+        date: Set(request_measurement.date),
+        kind: Set(request_measurement.kind),
+        value: Set(request_measurement.value),
+        unit: Set(request_measurement.unit.unwrap_or(user.preferred_unit)),
+        comment: Set(request_measurement.comment),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving the new measurement {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if complete_reached_goals(&database, user.id, &[GoalKind::Measurement])
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user.id);
+    }
+
+    Ok(Json(new_measurement.id))
+}
+
+pub async fn create_measurements(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_measurements): Json<Vec<RequestMeasurement>>,
+) -> Result<(), StatusCode> {
+    warn!(
+        "{} measurements imported by user: {}",
+        request_measurements.len(),
+        user.username
+    );
+
+    // A daily weigh-in for a hundred years is still well below this
+    if request_measurements.len() > 365_000 {
+        warn!("cannot import more than 365000 measurements");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if request_measurements.is_empty() {
+        return Ok(());
+    }
+
+    let mut measurements_to_insert = Vec::new();
+
+    for request_measurement in request_measurements {
+        if !is_valid_measurement(request_measurement.kind, request_measurement.value) {
+            warn!("invalid measurement value {}", request_measurement.value);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+
+        measurements_to_insert.push(measurements::ActiveModel {
+            date: Set(request_measurement.date),
+            kind: Set(request_measurement.kind),
+            value: Set(request_measurement.value),
+            unit: Set(request_measurement.unit.unwrap_or(user.preferred_unit)),
+            comment: Set(request_measurement.comment),
+            user_id: Set(Some(user.id)),
+            ..Default::default()
+        });
+    }
+
+    Measurements::insert_many(measurements_to_insert)
+        .exec(&database)
+        .await
+        .map_err(|err| {
+            error!("error importing measurements {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if complete_reached_goals(&database, user.id, &[GoalKind::Measurement])
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user.id);
+    }
+
+    Ok(())
+}
+
+pub async fn get_all_measurements(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(query): Query<MeasurementQuery>,
+) -> Result<Json<Vec<ResponseMeasurement>>, StatusCode> {
+    let unit = resolve_unit(query.unit, &user);
+
+    let measurements: Vec<ResponseMeasurement> = find_measurements(&database, user.id, &query)
+        .await?
+        .into_iter()
+        .map(|measurement| to_response(measurement, unit))
+        .collect();
+
+    warn!(
+        "{} measurements fetched by user: {}",
+        measurements.len(),
+        user.username
+    );
+
+    Ok(Json(measurements))
+}
+
+pub async fn get_one_measurement(
+    Extension(user): Extension<Model>,
+    Path(measurement_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(query): Query<MeasurementQuery>,
+) -> Result<Json<ResponseMeasurement>, StatusCode> {
+    warn!("measurement fetched by user: {}", user.username);
+    let unit = resolve_unit(query.unit, &user);
+
+    let measurement = Measurements::find_by_id(measurement_id)
+        .filter(measurements::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let Some(measurement) = measurement {
+        return Ok(Json(to_response(measurement, unit)));
+    }
+
+    Err(StatusCode::NOT_FOUND)
+}
+
+// One point per local day (the last reading of that day), in the user's timezone
+pub async fn get_measurement_series(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(query): Query<MeasurementQuery>,
+) -> Result<Json<ResponseSeries>, StatusCode> {
+    let Some(kind) = query.kind else {
+        warn!("measurement series requested without a kind");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let unit = resolve_unit(query.unit, &user);
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+
+    let mut points: Vec<SeriesPoint> = Vec::new();
+    for measurement in find_measurements(&database, user.id, &query).await? {
+        let date = local_date(&measurement.date, timezone);
+        let value = convert_measurement(kind, measurement.value, measurement.unit, unit);
+
+        match points.last_mut() {
+            Some(point) if point.date == date => point.value = value,
+            _ => points.push(SeriesPoint { date, value }),
+        }
+    }
+
+    warn!(
+        "{} measurement points fetched by user: {}",
+        points.len(),
+        user.username
+    );
+
+    Ok(Json(ResponseSeries { kind, unit, points }))
+}
+
+pub async fn update_measurement(
+    Extension(user): Extension<Model>,
+    Path(measurement_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_measurement): Json<RequestMeasurement>,
+) -> Result<(), StatusCode> {
+    warn!("measurement updated by user: {}", user.username);
+
+    if !is_valid_measurement(request_measurement.kind, request_measurement.value) {
+        warn!("invalid measurement value {}", request_measurement.value);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let update_measurement = measurements::ActiveModel {
+        id: Set(measurement_id),
+        date: Set(request_measurement.date),
+        kind: Set(request_measurement.kind),
+        value: Set(request_measurement.value),
+        unit: Set(request_measurement.unit.unwrap_or(user.preferred_unit)),
+        comment: Set(request_measurement.comment),
+        ..Default::default()
+    };
+
+    Measurements::update(update_measurement)
+        .filter(measurements::Column::UserId.eq(user.id))
+        .filter(measurements::Column::Id.eq(measurement_id))
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if complete_reached_goals(&database, user.id, &[GoalKind::Measurement])
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user.id);
+    }
+
+    Ok(())
+}
+
+pub async fn delete_measurement(
+    Extension(user): Extension<Model>,
+    Path(measurement_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("measurement deleted by user: {}", user.username);
+
+    let result = Measurements::delete_many()
+        .filter(measurements::Column::UserId.eq(user.id))
+        .filter(measurements::Column::Id.eq(measurement_id))
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(())
+}
@@ -1,35 +1,99 @@
 // My custom routes
+mod achievements;
+mod calendar;
+mod cardio_sessions;
+mod categories;
 mod create_exercise;
 mod create_workout_set;
 mod delete_exercise;
 mod delete_set;
+mod email_previews;
+mod equipment;
+mod exercise_library;
+mod exercise_progression;
+mod exercise_search;
 mod get_exercises;
 mod get_workout_sets;
+mod goals;
 mod guard;
+mod plates;
+mod programs;
 mod request_logger;
+mod rest_stats;
+mod routines;
+mod scores;
+mod stall_alerts;
+mod tasks;
 mod hello_world;
+mod measurements;
+mod muscles;
 mod update_exercises;
 mod update_sets;
+mod user_profile;
 mod users;
+mod workouts;
 
+use achievements::get_achievements;
 use axum::routing::delete;
+use axum::routing::patch;
 use axum::routing::put;
+use calendar::get_calendar;
+use cardio_sessions::{
+    get_all_cardio_sessions, get_one_cardio_session, import_cardio_session, MAX_TRACK_FILE_BYTES,
+};
+pub use categories::seed_system_categories;
+use categories::{create_category, delete_category, get_all_categories};
 use create_exercise::create_exercise;
 use create_workout_set::{create_workout_set, create_workout_sets};
 use delete_exercise::delete_exercise;
 use delete_set::{delete_set, delete_sets};
+use email_previews::preview_email;
+use equipment::{
+    activate_equipment_profile, create_equipment_profile, delete_equipment_profile,
+    get_equipment_profiles, get_library_substitutes, get_substitutes, update_equipment_profile,
+};
+pub use exercise_library::seed_exercise_library;
+use exercise_library::{get_library, get_one_library_exercise, override_library_exercise};
+use exercise_progression::get_next_session;
+use exercise_search::search_exercises;
 use get_exercises::{get_all_exercises, get_one_exercise};
 use get_workout_sets::{get_all_workout_sets, get_one_workout_set};
+use goals::{create_goal, delete_goal, get_all_goals, get_one_goal, update_goal};
 use guard::guard;
+use plates::{calculate_plates, get_plate_inventories, update_plate_inventory};
+use programs::{
+    complete_program_day, create_program, delete_enrollment, delete_program, enroll_in_program,
+    get_all_enrollments, get_all_programs, get_next_workout, get_one_program,
+};
 use request_logger::request_logger;
+use rest_stats::get_rest_stats;
+use routines::{
+    clone_routine, create_routine, delete_routine, get_all_routines, get_one_routine,
+    start_routine, update_routine,
+};
+use scores::get_scores;
+pub use stall_alerts::spawn_stall_detection;
+use stall_alerts::{dismiss_stall_alert, get_stall_alerts, refresh_stall_alerts};
+use tasks::{
+    complete_task, create_task, delete_task, get_all_tasks, get_one_task, get_task_history,
+    restore_task, uncomplete_task, update_task,
+};
 use hello_world::{hello_world, privacy_policy, account_delete};
+use measurements::{
+    create_measurement, create_measurements, delete_measurement, get_all_measurements,
+    get_measurement_series, get_one_measurement, update_measurement,
+};
+use muscles::{get_exercise_muscles, get_muscle_volume, update_exercise_muscles};
 use update_exercises::atomic_update_exercise;
 use update_sets::{atomic_update_set, atomic_update_sets};
+use user_profile::{get_profile, update_profile};
 use users::{
     change_password, create_user, login, logout, request_email_verification,
-    request_password_reset, verify_email,
+    request_password_reset, update_preferred_unit, verify_email,
 };
+use workouts::get_workout;
 
+use axum::extract::DefaultBodyLimit;
 use axum::http::Method;
 use axum::middleware;
 use std::net::SocketAddr;
@@ -60,8 +124,11 @@ pub async fn create_routes(database: DatabaseConnection) -> Router {
         message: String::from("Hello from shared data"),
     };
 
-    Router::new()
+    let router = Router::new()
         .route("/users/logout", post(logout))
+        .route("/users/preferred-unit", put(update_preferred_unit))
+        .route("/users/me", get(get_profile))
+        .route("/users/me", patch(update_profile))
         .route("/sets", post(create_workout_set))
         .route("/sets/bulk", post(create_workout_sets))
         .route("/sets/bulk", delete(delete_sets))
@@ -70,6 +137,116 @@ pub async fn create_routes(database: DatabaseConnection) -> Router {
         .route("/sets/:set_id", get(get_one_workout_set))
         .route("/sets/:set_id", delete(delete_set))
         .route("/sets/:set_id", put(atomic_update_set))
+        .route("/workouts/:date", get(get_workout))
+        .route("/exercises", post(create_exercise))
+        .route("/exercises", get(get_all_exercises))
+        .route("/exercises/search", get(search_exercises))
+        .route("/exercises/:exercise_id", get(get_one_exercise))
+        .route("/exercises/:exercise_id", put(atomic_update_exercise))
+        .route("/exercises/:exercise_id", delete(delete_exercise))
+        .route("/exercises/:exercise_id/next", get(get_next_session))
+        .route("/exercises/:exercise_id/substitutes", get(get_substitutes))
+        .route("/exercises/:exercise_id/muscles", get(get_exercise_muscles))
+        .route(
+            "/exercises/:exercise_id/muscles",
+            put(update_exercise_muscles),
+        )
+        .route("/equipment-profiles", get(get_equipment_profiles))
+        .route("/equipment-profiles", post(create_equipment_profile))
+        .route(
+            "/equipment-profiles/:profile_id",
+            put(update_equipment_profile),
+        )
+        .route(
+            "/equipment-profiles/:profile_id",
+            delete(delete_equipment_profile),
+        )
+        .route(
+            "/equipment-profiles/:profile_id/activate",
+            post(activate_equipment_profile),
+        )
+        .route("/library", get(get_library))
+        .route(
+            "/library/:library_exercise_id",
+            get(get_one_library_exercise),
+        )
+        .route(
+            "/library/:library_exercise_id",
+            put(override_library_exercise),
+        )
+        .route(
+            "/library/:library_exercise_id/substitutes",
+            get(get_library_substitutes),
+        )
+        .route("/tasks", get(get_all_tasks))
+        .route("/tasks", post(create_task))
+        .route("/tasks/:task_id", get(get_one_task))
+        .route("/tasks/:task_id", put(update_task))
+        .route("/tasks/:task_id", delete(delete_task))
+        .route("/tasks/:task_id/complete", post(complete_task))
+        .route("/tasks/:task_id/uncomplete", post(uncomplete_task))
+        .route("/tasks/:task_id/restore", post(restore_task))
+        .route("/tasks/:task_id/completions", get(get_task_history))
+        .route("/achievements", get(get_achievements))
+        .route("/goals", get(get_all_goals))
+        .route("/goals", post(create_goal))
+        .route("/goals/:goal_id", get(get_one_goal))
+        .route("/goals/:goal_id", put(update_goal))
+        .route("/goals/:goal_id", delete(delete_goal))
+        .route("/plates", get(get_plate_inventories))
+        .route("/plates/calculate", get(calculate_plates))
+        .route("/plates/:unit", put(update_plate_inventory))
+        .route("/categories", get(get_all_categories))
+        .route("/categories", post(create_category))
+        .route("/categories/:category_id", delete(delete_category))
+        .route("/measurements", post(create_measurement))
+        .route("/measurements", get(get_all_measurements))
+        .route("/measurements/bulk", post(create_measurements))
+        .route("/measurements/series", get(get_measurement_series))
+        .route("/measurements/:measurement_id", get(get_one_measurement))
+        .route("/measurements/:measurement_id", put(update_measurement))
+        .route("/measurements/:measurement_id", delete(delete_measurement))
+        .route("/stats/scores", get(get_scores))
+        .route("/stats/calendar", get(get_calendar))
+        .route("/stats/muscles", get(get_muscle_volume))
+        .route("/stats/rest", get(get_rest_stats))
+        .route("/stats/stalls", get(get_stall_alerts))
+        .route("/stats/stalls/refresh", post(refresh_stall_alerts))
+        .route("/stats/stalls/:alert_id/dismiss", post(dismiss_stall_alert))
+        .route("/routines", post(create_routine))
+        .route("/routines", get(get_all_routines))
+        .route("/routines/:routine_id", get(get_one_routine))
+        .route("/routines/:routine_id", put(update_routine))
+        .route("/routines/:routine_id", delete(delete_routine))
+        .route("/routines/:routine_id/clone", post(clone_routine))
+        .route("/routines/:routine_id/start", post(start_routine))
+        .route("/cardio-sessions", get(get_all_cardio_sessions))
+        .route(
+            "/cardio-sessions/import",
+            post(import_cardio_session).layer(DefaultBodyLimit::max(MAX_TRACK_FILE_BYTES)),
+        )
+        .route(
+            "/cardio-sessions/:cardio_session_id",
+            get(get_one_cardio_session),
+        )
+        .route("/programs", get(get_all_programs))
+        .route("/programs", post(create_program))
+        .route("/programs/:program_key", get(get_one_program))
+        .route("/programs/:program_key", delete(delete_program))
+        .route("/programs/:program_key/enroll", post(enroll_in_program))
+        .route("/program-enrollments", get(get_all_enrollments))
+        .route(
+            "/program-enrollments/:enrollment_id",
+            delete(delete_enrollment),
+        )
+        .route(
+            "/program-enrollments/:enrollment_id/next",
+            get(get_next_workout),
+        )
+        .route(
+            "/program-enrollments/:enrollment_id/complete",
+            post(complete_program_day),
+        )
         .route_layer(middleware::from_fn(guard))
         .route(
             "/users/request-password-reset",
@@ -96,5 +273,11 @@ pub async fn create_routes(database: DatabaseConnection) -> Router {
                     .level(Level::WARN))
                 .on_response(trace::DefaultOnResponse::new()
                     .level(Level::WARN)),)
-        .layer(Extension(database))
+        .layer(Extension(database));
+
+    // Email previews are only served by debug builds
+    if cfg!(debug_assertions) {
+        return router.route("/dev/emails/:template", get(preview_email));
+    }
+    router
 }
@@ -0,0 +1,265 @@
+use crate::database::sea_orm_active_enums::{Muscle, MuscleRole, SetType};
+use crate::database::users::Model;
+use crate::database::{
+    exercise_muscles, exercise_muscles::Entity as ExerciseMuscles, exercises,
+    exercises::Entity as Exercises, workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::muscles::{fallback_shares, set_share};
+use crate::utils::set_filter::SetTypeFilter;
+use crate::utils::time::{local_date, parse_timezone, week_start_date};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveEnum, ColumnTrait, DatabaseConnection, EntityTrait, Iterable, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct MuscleMapping {
+    pub primary: Vec<Muscle>,
+    #[serde(default)]
+    pub secondary: Vec<Muscle>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MuscleVolumeQuery {
+    pub weeks: Option<i64>,
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MuscleWeek {
+    pub week_start: NaiveDate,
+    pub sets: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseMuscleVolume {
+    pub weeks: Vec<MuscleWeek>,
+    pub average: BTreeMap<String, f64>,
+    // Exercises counted through their category because they have no muscle mapping
+    pub unmapped_exercises: Vec<String>,
+}
+
+async fn find_exercise(
+    database: &DatabaseConnection,
+    user_id: i32,
+    exercise_id: i32,
+) -> Result<exercises::Model, StatusCode> {
+    Exercises::find_by_id(exercise_id)
+        .filter(exercises::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+fn empty_week() -> BTreeMap<String, f64> {
+    Muscle::iter()
+        .map(|muscle| (muscle.to_value(), 0.0))
+        .collect()
+}
+
+pub async fn get_exercise_muscles(
+    Extension(user): Extension<Model>,
+    Path(exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<MuscleMapping>, StatusCode> {
+    warn!("exercise muscles fetched by user: {}", user.username);
+
+    let exercise = find_exercise(&database, user.id, exercise_id).await?;
+    let mut mapping = MuscleMapping::default();
+
+    for exercise_muscle in ExerciseMuscles::find()
+        .filter(exercise_muscles::Column::ExerciseId.eq(exercise.id))
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        match exercise_muscle.role {
+            MuscleRole::Primary => mapping.primary.push(exercise_muscle.muscle),
+            MuscleRole::Secondary => mapping.secondary.push(exercise_muscle.muscle),
+        }
+    }
+
+    Ok(Json(mapping))
+}
+
+// Replaces the whole mapping, an empty primary list goes back to the category fallback
+pub async fn update_exercise_muscles(
+    Extension(user): Extension<Model>,
+    Path(exercise_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(mapping): Json<MuscleMapping>,
+) -> Result<(), StatusCode> {
+    warn!("exercise muscles updated by user: {}", user.username);
+
+    if mapping.primary.is_empty() && !mapping.secondary.is_empty() {
+        warn!("secondary muscles given without a primary muscle");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if mapping
+        .secondary
+        .iter()
+        .any(|muscle| mapping.primary.contains(muscle))
+    {
+        warn!("muscle listed as both primary and secondary");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let exercise = find_exercise(&database, user.id, exercise_id).await?;
+
+    let mut exercise_muscles_to_insert = Vec::new();
+    for (muscles, role) in [
+        (&mapping.primary, MuscleRole::Primary),
+        (&mapping.secondary, MuscleRole::Secondary),
+    ] {
+        let unique_muscles: Vec<Muscle> = Muscle::iter()
+            .filter(|muscle| muscles.contains(muscle))
+            .collect();
+        for muscle in unique_muscles {
+            exercise_muscles_to_insert.push(exercise_muscles::ActiveModel {
+                exercise_id: Set(exercise.id),
+                muscle: Set(muscle),
+                role: Set(role),
+                ..Default::default()
+            });
+        }
+    }
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    ExerciseMuscles::delete_many()
+        .filter(exercise_muscles::Column::ExerciseId.eq(exercise.id))
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !exercise_muscles_to_insert.is_empty() {
+        ExerciseMuscles::insert_many(exercise_muscles_to_insert)
+            .exec(&txn)
+            .await
+            .map_err(|err| {
+                error!("error saving exercise muscles {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+// Fractional sets per muscle and week for the last `weeks` weeks, the current one included
+pub async fn get_muscle_volume(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(query): Query<MuscleVolumeQuery>,
+) -> Result<Json<ResponseMuscleVolume>, StatusCode> {
+    warn!("muscle volume fetched by user: {}", user.username);
+
+    let weeks = query.weeks.unwrap_or(4);
+    if !(1..=52).contains(&weeks) {
+        warn!("invalid number of weeks {}", weeks);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let set_type_filter = SetTypeFilter::from_query(
+        query.include_types.as_deref(),
+        query.exclude_types.as_deref(),
+        &[SetType::WarmUp],
+    )?;
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let first_week = week_start_date(today, profile.week_start) - Duration::weeks(weeks - 1);
+
+    let exercises = Exercises::find()
+        .filter(exercises::Column::UserId.eq(user.id))
+        .find_with_related(ExerciseMuscles)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mappings: HashMap<String, Vec<(Muscle, MuscleRole)>> = exercises
+        .into_iter()
+        .filter(|(_, exercise_muscles)| !exercise_muscles.is_empty())
+        .map(|(exercise, exercise_muscles)| {
+            let muscles = exercise_muscles
+                .into_iter()
+                .map(|exercise_muscle| (exercise_muscle.muscle, exercise_muscle.role))
+                .collect();
+            (exercise.name, muscles)
+        })
+        .collect();
+
+    // A day of slack on the database side, the exact cut happens on local dates below
+    let since: DateTimeWithTimeZone =
+        (Utc::now() - Duration::weeks(weeks) - Duration::days(1)).into();
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::Date.gte(since))
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut volume: BTreeMap<NaiveDate, BTreeMap<String, f64>> = (0..weeks)
+        .map(|week| (first_week + Duration::weeks(week), empty_week()))
+        .collect();
+    let mut unmapped_exercises = BTreeSet::new();
+
+    for workout_set in workout_sets {
+        if !set_type_filter.includes(workout_set.set_type) {
+            continue;
+        }
+        let week = week_start_date(local_date(&workout_set.date, timezone), profile.week_start);
+        let Some(week_volume) = volume.get_mut(&week) else {
+            continue;
+        };
+
+        let shares: Vec<(Muscle, f64)> = match mappings.get(&workout_set.exercise_name) {
+            Some(muscles) => muscles
+                .iter()
+                .map(|(muscle, role)| (*muscle, set_share(*role)))
+                .collect(),
+            None => {
+                unmapped_exercises.insert(workout_set.exercise_name.clone());
+                fallback_shares(&workout_set.category)
+            }
+        };
+
+        for (muscle, share) in shares {
+            *week_volume.entry(muscle.to_value()).or_default() += share;
+        }
+    }
+
+    let mut average = empty_week();
+    for week_volume in volume.values() {
+        for (muscle, sets) in week_volume {
+            *average.entry(muscle.clone()).or_default() += sets / weeks as f64;
+        }
+    }
+
+    Ok(Json(ResponseMuscleVolume {
+        weeks: volume
+            .into_iter()
+            .map(|(week_start, sets)| MuscleWeek { week_start, sets })
+            .collect(),
+        average,
+        unmapped_exercises: unmapped_exercises.into_iter().collect(),
+    }))
+}
@@ -0,0 +1,177 @@
+use crate::database::sea_orm_active_enums::WeightUnit;
+use crate::database::users::Model;
+use crate::database::{plate_inventories, plate_inventories::Entity as PlateInventories};
+use crate::utils::plates::{
+    default_bar_weight, default_plates, load_bar, validate_inventory, warm_up_ramp, Plate,
+    WarmUpSet,
+};
+use crate::utils::units::resolve_unit;
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use log::{error, warn};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, Iterable,
+    QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Deserialize, Debug)]
+pub struct RequestPlateInventory {
+    pub bar_weight: f64,
+    pub plates: Vec<Plate>,
+}
+
+#[derive(Serialize)]
+pub struct ResponsePlateInventory {
+    pub unit: WeightUnit,
+    pub bar_weight: f64,
+    pub plates: Vec<Plate>,
+    // True until the user stores their own inventory for this unit
+    pub is_default: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlatesQuery {
+    pub weight: f64,
+    // Overrides the stored bar, e.g. for a lighter technique bar
+    pub bar_weight: Option<f64>,
+    pub unit: Option<WeightUnit>,
+}
+
+#[derive(Serialize)]
+pub struct ResponsePlates {
+    pub target_weight: f64,
+    pub unit: WeightUnit,
+    pub bar_weight: f64,
+    // What actually goes on the bar, can be below the target when the plates don't add up
+    pub weight: f64,
+    pub remainder: f64,
+    pub per_side: Vec<Plate>,
+    pub warm_up: Vec<WarmUpSet>,
+}
+
+async fn find_inventory(
+    database: &DatabaseConnection,
+    user_id: i32,
+    unit: WeightUnit,
+) -> Result<Option<plate_inventories::Model>, StatusCode> {
+    PlateInventories::find()
+        .filter(plate_inventories::Column::UserId.eq(user_id))
+        .filter(plate_inventories::Column::Unit.eq(unit))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn to_response(
+    unit: WeightUnit,
+    inventory: Option<plate_inventories::Model>,
+) -> ResponsePlateInventory {
+    match inventory {
+        Some(inventory) => ResponsePlateInventory {
+            unit,
+            bar_weight: inventory.bar_weight,
+            plates: serde_json::from_value(inventory.plates).unwrap_or_default(),
+            is_default: false,
+        },
+        None => ResponsePlateInventory {
+            unit,
+            bar_weight: default_bar_weight(unit),
+            plates: default_plates(unit),
+            is_default: true,
+        },
+    }
+}
+
+// One inventory per unit, a gym with both kg and lb plates keeps two
+pub async fn get_plate_inventories(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponsePlateInventory>>, StatusCode> {
+    warn!("plate inventories fetched by user: {}", user.username);
+
+    let mut inventories = Vec::new();
+    for unit in WeightUnit::iter() {
+        let inventory = find_inventory(&database, user.id, unit).await?;
+        inventories.push(to_response(unit, inventory));
+    }
+
+    Ok(Json(inventories))
+}
+
+pub async fn update_plate_inventory(
+    Extension(user): Extension<Model>,
+    Path(unit): Path<WeightUnit>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_inventory): Json<RequestPlateInventory>,
+) -> Result<(), StatusCode> {
+    warn!("plate inventory updated by user: {}", user.username);
+
+    validate_inventory(request_inventory.bar_weight, &request_inventory.plates).map_err(
+        |reason| {
+            warn!("invalid plate inventory: {}", reason);
+            StatusCode::BAD_REQUEST
+        },
+    )?;
+
+    let result = match find_inventory(&database, user.id, unit).await? {
+        Some(inventory) => {
+            let mut inventory = inventory.into_active_model();
+            inventory.bar_weight = Set(request_inventory.bar_weight);
+            inventory.plates = Set(json!(request_inventory.plates));
+            inventory.update(&database).await.map(|_| ())
+        }
+        None => plate_inventories::ActiveModel {
+            unit: Set(unit),
+            bar_weight: Set(request_inventory.bar_weight),
+            plates: Set(json!(request_inventory.plates)),
+            user_id: Set(Some(user.id)),
+            ..Default::default()
+        }
+        .insert(&database)
+        .await
+        .map(|_| ()),
+    };
+
+    result.map_err(|err| {
+        error!("error saving the plate inventory {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// Plates per side and a warm-up ramp for the target weight, using the user's inventory
+pub async fn calculate_plates(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(plates_query): Query<PlatesQuery>,
+) -> Result<Json<ResponsePlates>, StatusCode> {
+    let unit = resolve_unit(plates_query.unit, &user);
+    let inventory = to_response(unit, find_inventory(&database, user.id, unit).await?);
+    let bar_weight = plates_query.bar_weight.unwrap_or(inventory.bar_weight);
+
+    let target_weight = plates_query.weight;
+    if !(0.0..=1000.0).contains(&target_weight) || !(0.0..=100.0).contains(&bar_weight) {
+        warn!("invalid plate calculation {:?}", plates_query);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let load = load_bar(target_weight, bar_weight, &inventory.plates);
+    warn!(
+        "plates calculated for {} {:?} by user: {}",
+        target_weight, unit, user.username
+    );
+
+    Ok(Json(ResponsePlates {
+        target_weight,
+        unit,
+        bar_weight,
+        weight: load.weight,
+        remainder: (target_weight - load.weight).max(0.0),
+        per_side: load.per_side,
+        warm_up: warm_up_ramp(target_weight, bar_weight, &inventory.plates),
+    }))
+}
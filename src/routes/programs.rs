@@ -0,0 +1,562 @@
+use crate::database::sea_orm_active_enums::{SetType, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    program_enrollments, program_enrollments::Entity as ProgramEnrollments, programs,
+    programs::Entity as Programs, workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::routines::PlannedSet;
+use crate::utils::programs::{builtin_programs, LiftState, LoggedSet, ProgramDefinition};
+use crate::utils::strength::estimated_one_rep_max;
+use crate::utils::units::{convert_weight, resolve_unit, UnitQuery};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Utc;
+use log::{error, warn};
+use sea_orm::prelude::{DateTimeWithTimeZone, Json as JsonValue};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const CUSTOM_PREFIX: &str = "custom-";
+
+#[derive(Deserialize, Debug)]
+pub struct RequestEnrollment {
+    // Keyed by the program's lift names, lifts left out are estimated from logged sets
+    #[serde(default)]
+    pub training_maxes: BTreeMap<String, f64>,
+    pub unit: Option<WeightUnit>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseProgram {
+    pub key: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub builtin: bool,
+}
+
+#[derive(Serialize)]
+pub struct ResponseEnrollment {
+    pub id: i32,
+    pub program_key: String,
+    pub program_name: String,
+    pub week: i32,
+    pub day: i32,
+    pub started_at: DateTimeWithTimeZone,
+    pub training_maxes: BTreeMap<String, f64>,
+    pub unit: WeightUnit,
+}
+
+#[derive(Serialize)]
+pub struct ResponseNextWorkout {
+    pub enrollment: ResponseEnrollment,
+    pub day_name: String,
+    pub sets: Vec<PlannedSet>,
+}
+
+fn from_json<T: DeserializeOwned>(value: JsonValue) -> Result<T, StatusCode> {
+    serde_json::from_value(value).map_err(|err| {
+        error!("error deserializing stored program data {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<JsonValue, StatusCode> {
+    serde_json::to_value(value).map_err(|err| {
+        error!("error serializing program data {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// Built-in programs are addressed by their slug, user programs by "custom-<id>"
+async fn find_program(
+    database: &DatabaseConnection,
+    user_id: i32,
+    program_key: &str,
+) -> Result<ProgramDefinition, StatusCode> {
+    if let Some(program_id) = program_key.strip_prefix(CUSTOM_PREFIX) {
+        let program_id: i32 = program_id.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+        let program = Programs::find_by_id(program_id)
+            .filter(programs::Column::UserId.eq(user_id))
+            .one(database)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+
+        return from_json(program.definition);
+    }
+
+    builtin_programs()
+        .iter()
+        .find(|(key, _)| key == program_key)
+        .map(|(_, definition)| definition.clone())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn find_enrollment(
+    database: &DatabaseConnection,
+    user_id: i32,
+    enrollment_id: i32,
+) -> Result<program_enrollments::Model, StatusCode> {
+    ProgramEnrollments::find_by_id(enrollment_id)
+        .filter(program_enrollments::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Training max from the best estimated 1RM logged for the lift's exercise
+async fn estimate_training_max(
+    database: &DatabaseConnection,
+    user_id: i32,
+    definition: &ProgramDefinition,
+    lift_key: &str,
+) -> Result<Option<f64>, StatusCode> {
+    let Some(lift) = definition.lifts.get(lift_key) else {
+        return Ok(None);
+    };
+
+    let best_one_rep_max = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .filter(workout_sets::Column::ExerciseName.eq(lift.exercise_name.clone()))
+        .filter(workout_sets::Column::SetType.ne(SetType::WarmUp))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter(|workout_set| workout_set.reps > 0)
+        .map(|workout_set| {
+            estimated_one_rep_max(
+                convert_weight(workout_set.weight, workout_set.weight_unit, definition.unit),
+                workout_set.reps,
+            )
+        })
+        .reduce(f64::max);
+
+    Ok(best_one_rep_max.map(|one_rep_max| {
+        definition.round_weight(one_rep_max * lift.training_max_percent / 100.0)
+    }))
+}
+
+fn to_response(
+    enrollment: &program_enrollments::Model,
+    definition: &ProgramDefinition,
+    lift_states: &BTreeMap<String, LiftState>,
+    unit: WeightUnit,
+) -> ResponseEnrollment {
+    ResponseEnrollment {
+        id: enrollment.id,
+        program_key: enrollment.program_key.clone(),
+        program_name: definition.name.clone(),
+        week: enrollment.current_week + 1,
+        day: enrollment.current_day + 1,
+        started_at: enrollment.started_at,
+        training_maxes: lift_states
+            .iter()
+            .map(|(key, state)| {
+                (
+                    key.clone(),
+                    convert_weight(state.training_max, definition.unit, unit),
+                )
+            })
+            .collect(),
+        unit,
+    }
+}
+
+// Walks through every program day whose sets have been logged since the current day started,
+// progressing or deloading lifts as it goes. Forcing closes the current day even if sets are
+// missing, which counts them as misses.
+async fn advance_enrollment(
+    database: &DatabaseConnection,
+    enrollment: program_enrollments::Model,
+    force: bool,
+) -> Result<program_enrollments::Model, StatusCode> {
+    let definition: ProgramDefinition = from_json(enrollment.definition.clone())?;
+    let mut lift_states: BTreeMap<String, LiftState> = from_json(enrollment.lift_states.clone())?;
+
+    let logged_sets: Vec<LoggedSet> = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(enrollment.user_id))
+        .filter(workout_sets::Column::Date.gt(enrollment.day_started_at))
+        .filter(workout_sets::Column::SetType.ne(SetType::WarmUp))
+        .order_by_asc(workout_sets::Column::Date)
+        .order_by_asc(workout_sets::Column::Id)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|workout_set| LoggedSet {
+            weight: convert_weight(workout_set.weight, workout_set.weight_unit, definition.unit),
+            exercise_name: workout_set.exercise_name,
+            reps: workout_set.reps,
+            date: workout_set.date,
+        })
+        .collect();
+
+    let (mut week, mut day) = (
+        enrollment.current_week as usize,
+        enrollment.current_day as usize,
+    );
+    let mut day_started_at = enrollment.day_started_at;
+    let mut remaining = logged_sets;
+    let mut advanced = false;
+
+    while let Some(outcome) = definition.evaluate_day(&lift_states, week, day, &remaining, force) {
+        definition.apply_day_outcome(&mut lift_states, &outcome);
+        let (next_week, next_day, cycle_completed) = definition.next_day(week, day);
+        if cycle_completed {
+            definition.apply_cycle_end(&mut lift_states);
+        }
+        (week, day) = (next_week, next_day);
+        advanced = true;
+
+        day_started_at = outcome
+            .finished_at
+            .unwrap_or_else(|| Utc::now().into())
+            .max(day_started_at);
+        // Leftover sets from the finished session don't carry over into the next day
+        remaining = remaining
+            .into_iter()
+            .enumerate()
+            .filter(|(index, logged_set)| {
+                !outcome.consumed.contains(index) && logged_set.date >= day_started_at
+            })
+            .map(|(_, logged_set)| logged_set)
+            .collect();
+
+        if force {
+            break;
+        }
+    }
+
+    if !advanced {
+        return Ok(enrollment);
+    }
+
+    let mut enrollment = enrollment.into_active_model();
+    enrollment.lift_states = Set(to_json(&lift_states)?);
+    enrollment.current_week = Set(week as i32);
+    enrollment.current_day = Set(day as i32);
+    enrollment.day_started_at = Set(day_started_at);
+
+    enrollment.update(database).await.map_err(|err| {
+        error!("error advancing program enrollment {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// Progression is driven by logged sets, so this runs after sets are created rather than when
+// the plan is read
+pub async fn advance_enrollments(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<(), StatusCode> {
+    let enrollments = ProgramEnrollments::find()
+        .filter(program_enrollments::Column::UserId.eq(user_id))
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for enrollment in enrollments {
+        advance_enrollment(database, enrollment, false).await?;
+    }
+
+    Ok(())
+}
+
+fn next_workout(
+    enrollment: &program_enrollments::Model,
+    unit: WeightUnit,
+) -> Result<ResponseNextWorkout, StatusCode> {
+    let definition: ProgramDefinition = from_json(enrollment.definition.clone())?;
+    let lift_states: BTreeMap<String, LiftState> = from_json(enrollment.lift_states.clone())?;
+    let (week, day) = (
+        enrollment.current_week as usize,
+        enrollment.current_day as usize,
+    );
+    let now: DateTimeWithTimeZone = Utc::now().into();
+
+    let mut sets = Vec::new();
+    for slot in definition.plan_day(&lift_states, week, day) {
+        for (index, planned_set) in slot.sets.iter().enumerate() {
+            sets.push(PlannedSet {
+                exercise_name: slot.exercise_name.clone(),
+                category: slot.category.clone(),
+                date: now,
+                set_number: index as i32 + 1,
+                reps: planned_set.reps,
+                weight: convert_weight(planned_set.weight, definition.unit, unit),
+                unit,
+                set_type: if planned_set.amrap {
+                    SetType::Amrap
+                } else {
+                    SetType::Working
+                },
+                rest_seconds: None,
+                comment: None,
+            });
+        }
+    }
+
+    Ok(ResponseNextWorkout {
+        enrollment: to_response(enrollment, &definition, &lift_states, unit),
+        day_name: definition
+            .day(week, day)
+            .map(|day| day.name.clone())
+            .unwrap_or_default(),
+        sets,
+    })
+}
+
+pub async fn get_all_programs(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<Vec<ResponseProgram>>, StatusCode> {
+    let mut response_programs: Vec<ResponseProgram> = builtin_programs()
+        .iter()
+        .map(|(key, definition)| ResponseProgram {
+            key: key.clone(),
+            name: definition.name.clone(),
+            description: definition.description.clone(),
+            builtin: true,
+        })
+        .collect();
+
+    let custom_programs = Programs::find()
+        .filter(programs::Column::UserId.eq(user.id))
+        .order_by_asc(programs::Column::Id)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for program in custom_programs {
+        let definition: ProgramDefinition = from_json(program.definition)?;
+        response_programs.push(ResponseProgram {
+            key: format!("{}{}", CUSTOM_PREFIX, program.id),
+            name: definition.name,
+            description: definition.description,
+            builtin: false,
+        });
+    }
+
+    warn!(
+        "{} programs fetched by user: {}",
+        response_programs.len(),
+        user.username
+    );
+
+    Ok(Json(response_programs))
+}
+
+pub async fn get_one_program(
+    Extension(user): Extension<Model>,
+    Path(program_key): Path<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ProgramDefinition>, StatusCode> {
+    warn!("program fetched by user: {}", user.username);
+
+    Ok(Json(find_program(&database, user.id, &program_key).await?))
+}
+
+pub async fn create_program(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(definition): Json<ProgramDefinition>,
+) -> Result<Json<String>, StatusCode> {
+    warn!("program created by user: {}", user.username);
+
+    if let Err(reason) = definition.validate() {
+        warn!("invalid program definition: {}", reason);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let program = programs::ActiveModel {
+        name: Set(definition.name.clone()),
+        definition: Set(to_json(&definition)?),
+        created_at: Set(Utc::now().into()),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving program {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(format!("{}{}", CUSTOM_PREFIX, program.id)))
+}
+
+// Enrollments keep their own copy of the definition, so deleting a program doesn't end them
+pub async fn delete_program(
+    Extension(user): Extension<Model>,
+    Path(program_key): Path<String>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("program deleted by user: {}", user.username);
+
+    let program_id: i32 = program_key
+        .strip_prefix(CUSTOM_PREFIX)
+        .and_then(|program_id| program_id.parse().ok())
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let result = Programs::delete_many()
+        .filter(programs::Column::Id.eq(program_id))
+        .filter(programs::Column::UserId.eq(user.id))
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(())
+}
+
+pub async fn enroll_in_program(
+    Extension(user): Extension<Model>,
+    Path(program_key): Path<String>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_enrollment): Json<RequestEnrollment>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("program enrollment created by user: {}", user.username);
+
+    let definition = find_program(&database, user.id, &program_key).await?;
+    let unit = resolve_unit(request_enrollment.unit, &user);
+
+    let mut lift_states = BTreeMap::new();
+    for lift_key in definition.lifts.keys() {
+        let training_max = match request_enrollment.training_maxes.get(lift_key) {
+            Some(training_max) if *training_max > 0.0 => {
+                Some(convert_weight(*training_max, unit, definition.unit))
+            }
+            Some(_) => return Err(StatusCode::BAD_REQUEST),
+            None => estimate_training_max(&database, user.id, &definition, lift_key).await?,
+        };
+
+        let Some(training_max) = training_max else {
+            warn!("no training max given or estimable for lift {}", lift_key);
+            return Err(StatusCode::BAD_REQUEST);
+        };
+
+        lift_states.insert(
+            lift_key.clone(),
+            LiftState {
+                training_max,
+                stage: 0,
+                failures: 0,
+            },
+        );
+    }
+
+    let now: DateTimeWithTimeZone = Utc::now().into();
+    let enrollment = program_enrollments::ActiveModel {
+        program_key: Set(program_key),
+        definition: Set(to_json(&definition)?),
+        lift_states: Set(to_json(&lift_states)?),
+        current_week: Set(0),
+        current_day: Set(0),
+        day_started_at: Set(now),
+        started_at: Set(now),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving program enrollment {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(enrollment.id))
+}
+
+pub async fn get_all_enrollments(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<Vec<ResponseEnrollment>>, StatusCode> {
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let enrollments = ProgramEnrollments::find()
+        .filter(program_enrollments::Column::UserId.eq(user.id))
+        .order_by_asc(program_enrollments::Column::StartedAt)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut response_enrollments = Vec::new();
+    for enrollment in enrollments {
+        let definition: ProgramDefinition = from_json(enrollment.definition.clone())?;
+        let lift_states = from_json(enrollment.lift_states.clone())?;
+        response_enrollments.push(to_response(&enrollment, &definition, &lift_states, unit));
+    }
+
+    warn!(
+        "{} program enrollments fetched by user: {}",
+        response_enrollments.len(),
+        user.username
+    );
+
+    Ok(Json(response_enrollments))
+}
+
+pub async fn get_next_workout(
+    Extension(user): Extension<Model>,
+    Path(enrollment_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<ResponseNextWorkout>, StatusCode> {
+    warn!("next program workout fetched by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let enrollment = find_enrollment(&database, user.id, enrollment_id).await?;
+
+    Ok(Json(next_workout(&enrollment, unit)?))
+}
+
+pub async fn complete_program_day(
+    Extension(user): Extension<Model>,
+    Path(enrollment_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<ResponseNextWorkout>, StatusCode> {
+    warn!("program day completed by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let enrollment = find_enrollment(&database, user.id, enrollment_id).await?;
+    // Catch up on fully logged days first so only the current one is forced
+    let enrollment = advance_enrollment(&database, enrollment, false).await?;
+    let enrollment = advance_enrollment(&database, enrollment, true).await?;
+
+    Ok(Json(next_workout(&enrollment, unit)?))
+}
+
+pub async fn delete_enrollment(
+    Extension(user): Extension<Model>,
+    Path(enrollment_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("program enrollment deleted by user: {}", user.username);
+
+    let result = ProgramEnrollments::delete_many()
+        .filter(program_enrollments::Column::Id.eq(enrollment_id))
+        .filter(program_enrollments::Column::UserId.eq(user.id))
+        .exec(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if result.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(())
+}
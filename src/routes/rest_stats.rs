@@ -0,0 +1,135 @@
+use crate::database::sea_orm_active_enums::SetType;
+use crate::database::users::Model;
+use crate::database::{workout_sets, workout_sets::Entity as WorkoutSets};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::set_filter::SetTypeFilter;
+use crate::utils::time::{local_date, parse_timezone};
+use axum::{extract::Query, http::StatusCode, Extension, Json};
+use chrono::{Duration, NaiveDate, Utc};
+use log::warn;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Deserialize, Debug)]
+pub struct RestStatsQuery {
+    pub weeks: Option<i64>,
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExerciseRest {
+    pub exercise_name: String,
+    pub average_rest_seconds: Option<f64>,
+    pub average_time_under_tension_seconds: Option<f64>,
+    pub sets: usize,
+}
+
+#[derive(Serialize)]
+pub struct WorkoutRest {
+    pub date: NaiveDate,
+    pub average_rest_seconds: Option<f64>,
+    pub total_rest_seconds: i64,
+    pub sets: usize,
+}
+
+#[derive(Serialize)]
+pub struct ResponseRestStats {
+    pub exercises: Vec<ExerciseRest>,
+    pub workouts: Vec<WorkoutRest>,
+}
+
+#[derive(Default)]
+struct Totals {
+    sets: usize,
+    rest: Vec<i32>,
+    time_under_tension: Vec<i32>,
+}
+
+impl Totals {
+    fn add(&mut self, workout_set: &workout_sets::Model) {
+        self.sets += 1;
+        self.rest.extend(workout_set.rest_seconds);
+        self.time_under_tension
+            .extend(workout_set.time_under_tension_seconds);
+    }
+}
+
+// Sets without timing data count toward `sets` but not toward the averages
+fn average(values: &[i32]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().map(|value| *value as f64).sum::<f64>() / values.len() as f64)
+}
+
+// Average rest per exercise and per workout day over the last `weeks` weeks
+pub async fn get_rest_stats(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(rest_query): Query<RestStatsQuery>,
+) -> Result<Json<ResponseRestStats>, StatusCode> {
+    warn!("rest stats fetched by user: {}", user.username);
+
+    let weeks = rest_query.weeks.unwrap_or(12);
+    if !(1..=520).contains(&weeks) {
+        warn!("invalid number of weeks {}", weeks);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // The short rests between warm-ups would drag every average down
+    let set_type_filter = SetTypeFilter::from_query(
+        rest_query.include_types.as_deref(),
+        rest_query.exclude_types.as_deref(),
+        &[SetType::WarmUp],
+    )?;
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+    let since: DateTimeWithTimeZone = (Utc::now() - Duration::weeks(weeks)).into();
+
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::Date.gte(since))
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut exercises: BTreeMap<String, Totals> = BTreeMap::new();
+    let mut workouts: BTreeMap<NaiveDate, Totals> = BTreeMap::new();
+    for workout_set in workout_sets
+        .iter()
+        .filter(|workout_set| set_type_filter.includes(workout_set.set_type))
+    {
+        exercises
+            .entry(workout_set.exercise_name.clone())
+            .or_default()
+            .add(workout_set);
+        workouts
+            .entry(local_date(&workout_set.date, timezone))
+            .or_default()
+            .add(workout_set);
+    }
+
+    Ok(Json(ResponseRestStats {
+        exercises: exercises
+            .into_iter()
+            .map(|(exercise_name, totals)| ExerciseRest {
+                exercise_name,
+                average_rest_seconds: average(&totals.rest),
+                average_time_under_tension_seconds: average(&totals.time_under_tension),
+                sets: totals.sets,
+            })
+            .collect(),
+        workouts: workouts
+            .into_iter()
+            .map(|(date, totals)| WorkoutRest {
+                date,
+                average_rest_seconds: average(&totals.rest),
+                total_rest_seconds: totals.rest.iter().map(|rest| *rest as i64).sum(),
+                sets: totals.sets,
+            })
+            .collect(),
+    }))
+}
@@ -0,0 +1,487 @@
+use crate::database::sea_orm_active_enums::{SetType, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    routine_exercises, routine_exercises::Entity as RoutineExercises, routines,
+    routines::Entity as Routines, workout_sets, workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::utils::units::{convert_weight, resolve_unit, UnitQuery};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Utc;
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct RequestRoutineExercise {
+    pub exercise_name: String,
+    pub category: String,
+    pub target_sets: i32,
+    pub target_reps: i32,
+    pub target_weight: Option<f64>,
+    pub unit: Option<WeightUnit>,
+    pub rest_seconds: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestRoutine {
+    pub name: String,
+    pub description: Option<String>,
+    pub exercises: Vec<RequestRoutineExercise>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestCloneRoutine {
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseRoutineExercise {
+    pub id: i32,
+    pub position: i32,
+    pub exercise_name: String,
+    pub category: String,
+    pub target_sets: i32,
+    pub target_reps: i32,
+    pub target_weight: Option<f64>,
+    pub unit: WeightUnit,
+    pub rest_seconds: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseRoutine {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTimeWithTimeZone,
+    pub exercises: Vec<ResponseRoutineExercise>,
+}
+
+// Shaped like the sets payload so a finished workout can be posted straight to /sets/bulk
+#[derive(Serialize)]
+pub struct PlannedSet {
+    pub exercise_name: String,
+    pub category: String,
+    pub date: DateTimeWithTimeZone,
+    pub set_number: i32,
+    pub reps: i32,
+    pub weight: f64,
+    pub unit: WeightUnit,
+    pub set_type: SetType,
+    pub rest_seconds: Option<i32>,
+    pub comment: Option<String>,
+}
+
+fn is_valid_routine(request_routine: &RequestRoutine) -> bool {
+    if request_routine.name.trim().is_empty() || request_routine.exercises.len() > 100 {
+        return false;
+    }
+
+    request_routine.exercises.iter().all(|exercise| {
+        !exercise.exercise_name.trim().is_empty()
+            && (1..=50).contains(&exercise.target_sets)
+            && exercise.target_reps >= 0
+            && exercise.target_weight.unwrap_or(0.0) >= 0.0
+            && exercise.rest_seconds.unwrap_or(0) >= 0
+    })
+}
+
+// Canonicalizes every exercise's category, rejecting ones the user doesn't have
+async fn resolve_exercise_categories(
+    database: &DatabaseConnection,
+    user_id: i32,
+    exercises: &mut [RequestRoutineExercise],
+) -> Result<(), StatusCode> {
+    let category_names = find_category_names(database, user_id).await?;
+    for exercise in exercises {
+        exercise.category = resolve_category(&category_names, &exercise.category)?;
+    }
+    Ok(())
+}
+
+async fn find_routine(
+    database: &DatabaseConnection,
+    user_id: i32,
+    routine_id: i32,
+) -> Result<routines::Model, StatusCode> {
+    Routines::find_by_id(routine_id)
+        .filter(routines::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn find_routine_exercises<C: ConnectionTrait>(
+    database: &C,
+    routine_id: i32,
+) -> Result<Vec<routine_exercises::Model>, StatusCode> {
+    RoutineExercises::find()
+        .filter(routine_exercises::Column::RoutineId.eq(routine_id))
+        .order_by_asc(routine_exercises::Column::Position)
+        .all(database)
+        .await
+        .map_err(|err| {
+            error!("error finding routine exercises {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+async fn insert_routine_exercises<C: ConnectionTrait>(
+    database: &C,
+    routine_id: i32,
+    exercises: Vec<routine_exercises::ActiveModel>,
+) -> Result<(), StatusCode> {
+    if exercises.is_empty() {
+        return Ok(());
+    }
+
+    RoutineExercises::insert_many(exercises)
+        .exec(database)
+        .await
+        .map_err(|err| {
+            error!("error saving exercises of routine {}: {}", routine_id, err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(())
+}
+
+fn to_active_exercises(
+    routine_id: i32,
+    exercises: Vec<RequestRoutineExercise>,
+    preferred_unit: WeightUnit,
+) -> Vec<routine_exercises::ActiveModel> {
+    exercises
+        .into_iter()
+        .enumerate()
+        .map(|(position, exercise)| routine_exercises::ActiveModel {
+            routine_id: Set(routine_id),
+            position: Set(position as i32),
+            exercise_name: Set(exercise.exercise_name),
+            category: Set(exercise.category),
+            target_sets: Set(exercise.target_sets),
+            target_reps: Set(exercise.target_reps),
+            target_weight: Set(exercise.target_weight),
+            weight_unit: Set(exercise.unit.unwrap_or(preferred_unit)),
+            rest_seconds: Set(exercise.rest_seconds),
+            notes: Set(exercise.notes),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn to_response(
+    routine: routines::Model,
+    exercises: Vec<routine_exercises::Model>,
+    unit: WeightUnit,
+) -> ResponseRoutine {
+    ResponseRoutine {
+        id: routine.id,
+        name: routine.name,
+        description: routine.description,
+        created_at: routine.created_at,
+        exercises: exercises
+            .into_iter()
+            .map(|exercise| ResponseRoutineExercise {
+                id: exercise.id,
+                position: exercise.position,
+                exercise_name: exercise.exercise_name,
+                category: exercise.category,
+                target_sets: exercise.target_sets,
+                target_reps: exercise.target_reps,
+                target_weight: exercise
+                    .target_weight
+                    .map(|weight| convert_weight(weight, exercise.weight_unit, unit)),
+                unit,
+                rest_seconds: exercise.rest_seconds,
+                notes: exercise.notes,
+            })
+            .collect(),
+    }
+}
+
+pub async fn create_routine(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(mut request_routine): Json<RequestRoutine>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("routine created by user: {}", user.username);
+
+    if !is_valid_routine(&request_routine) {
+        warn!("invalid routine {}", request_routine.name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    resolve_exercise_categories(&database, user.id, &mut request_routine.exercises).await?;
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let routine = routines::ActiveModel {
+        name: Set(request_routine.name),
+        description: Set(request_routine.description),
+        created_at: Set(Utc::now().into()),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| {
+        error!("error saving the new routine {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let exercises = to_active_exercises(routine.id, request_routine.exercises, user.preferred_unit);
+    insert_routine_exercises(&txn, routine.id, exercises).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(routine.id))
+}
+
+pub async fn get_all_routines(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<Vec<ResponseRoutine>>, StatusCode> {
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let routines = Routines::find()
+        .filter(routines::Column::UserId.eq(user.id))
+        .order_by_asc(routines::Column::Name)
+        .find_with_related(RoutineExercises)
+        .order_by_asc(routine_exercises::Column::Position)
+        .all(&database)
+        .await
+        .map_err(|err| {
+            error!("error finding routines {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    warn!(
+        "{} routines fetched by user: {}",
+        routines.len(),
+        user.username
+    );
+
+    Ok(Json(
+        routines
+            .into_iter()
+            .map(|(routine, exercises)| to_response(routine, exercises, unit))
+            .collect(),
+    ))
+}
+
+pub async fn get_one_routine(
+    Extension(user): Extension<Model>,
+    Path(routine_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<ResponseRoutine>, StatusCode> {
+    warn!("routine fetched by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let routine = find_routine(&database, user.id, routine_id).await?;
+    let exercises = find_routine_exercises(&database, routine.id).await?;
+
+    Ok(Json(to_response(routine, exercises, unit)))
+}
+
+// Replaces the routine's name, description and its whole exercise list
+pub async fn update_routine(
+    Extension(user): Extension<Model>,
+    Path(routine_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(mut request_routine): Json<RequestRoutine>,
+) -> Result<(), StatusCode> {
+    warn!("routine updated by user: {}", user.username);
+
+    if !is_valid_routine(&request_routine) {
+        warn!("invalid routine {}", request_routine.name);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    resolve_exercise_categories(&database, user.id, &mut request_routine.exercises).await?;
+
+    let routine = find_routine(&database, user.id, routine_id).await?;
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut routine: routines::ActiveModel = routine.into();
+    routine.name = Set(request_routine.name);
+    routine.description = Set(request_routine.description);
+    routine.update(&txn).await.map_err(|err| {
+        error!("error updating routine {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    RoutineExercises::delete_many()
+        .filter(routine_exercises::Column::RoutineId.eq(routine_id))
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let exercises = to_active_exercises(routine_id, request_routine.exercises, user.preferred_unit);
+    insert_routine_exercises(&txn, routine_id, exercises).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+pub async fn delete_routine(
+    Extension(user): Extension<Model>,
+    Path(routine_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("routine deleted by user: {}", user.username);
+
+    let routine = find_routine(&database, user.id, routine_id).await?;
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    RoutineExercises::delete_many()
+        .filter(routine_exercises::Column::RoutineId.eq(routine.id))
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Routines::delete_by_id(routine.id)
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+pub async fn clone_routine(
+    Extension(user): Extension<Model>,
+    Path(routine_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_clone): Json<RequestCloneRoutine>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("routine cloned by user: {}", user.username);
+
+    let routine = find_routine(&database, user.id, routine_id).await?;
+    let exercises = find_routine_exercises(&database, routine.id).await?;
+
+    let txn = database
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let cloned_routine = routines::ActiveModel {
+        name: Set(request_clone
+            .name
+            .unwrap_or(format!("{} (copy)", routine.name))),
+        description: Set(routine.description),
+        created_at: Set(Utc::now().into()),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await
+    .map_err(|err| {
+        error!("error saving the cloned routine {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let cloned_exercises = exercises
+        .into_iter()
+        .map(|exercise| routine_exercises::ActiveModel {
+            routine_id: Set(cloned_routine.id),
+            position: Set(exercise.position),
+            exercise_name: Set(exercise.exercise_name),
+            category: Set(exercise.category),
+            target_sets: Set(exercise.target_sets),
+            target_reps: Set(exercise.target_reps),
+            target_weight: Set(exercise.target_weight),
+            weight_unit: Set(exercise.weight_unit),
+            rest_seconds: Set(exercise.rest_seconds),
+            notes: Set(exercise.notes),
+            ..Default::default()
+        })
+        .collect();
+    insert_routine_exercises(&txn, cloned_routine.id, cloned_exercises).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(cloned_routine.id))
+}
+
+// Exercises without a target weight start from the last working set logged for them
+pub async fn start_routine(
+    Extension(user): Extension<Model>,
+    Path(routine_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<Vec<PlannedSet>>, StatusCode> {
+    warn!("workout started from routine by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let routine = find_routine(&database, user.id, routine_id).await?;
+    let exercises = find_routine_exercises(&database, routine.id).await?;
+    let now: DateTimeWithTimeZone = Utc::now().into();
+
+    let mut planned_sets = Vec::new();
+    for exercise in exercises {
+        let weight = match exercise.target_weight {
+            Some(weight) => convert_weight(weight, exercise.weight_unit, unit),
+            None => WorkoutSets::find()
+                .filter(workout_sets::Column::UserId.eq(user.id))
+                .filter(workout_sets::Column::ExerciseName.eq(exercise.exercise_name.clone()))
+                .filter(workout_sets::Column::SetType.eq(SetType::Working))
+                .order_by_desc(workout_sets::Column::Date)
+                .one(&database)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map_or(0.0, |last_set| {
+                    convert_weight(last_set.weight, last_set.weight_unit, unit)
+                }),
+        };
+
+        for set_number in 1..=exercise.target_sets {
+            planned_sets.push(PlannedSet {
+                exercise_name: exercise.exercise_name.clone(),
+                category: exercise.category.clone(),
+                date: now,
+                set_number,
+                reps: exercise.target_reps,
+                weight,
+                unit,
+                set_type: SetType::Working,
+                rest_seconds: exercise.rest_seconds,
+                comment: exercise.notes.clone(),
+            });
+        }
+    }
+
+    Ok(Json(planned_sets))
+}
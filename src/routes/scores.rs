@@ -0,0 +1,203 @@
+use crate::database::sea_orm_active_enums::{MeasurementKind, SetType, Sex, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{
+    measurements, measurements::Entity as Measurements, workout_sets,
+    workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::scores::{dots, ipf_gl, wilks, wilks2};
+use crate::utils::set_filter::SetTypeFilter;
+use crate::utils::strength::estimated_one_rep_max;
+use crate::utils::time::{local_date, parse_timezone};
+use crate::utils::units::{convert_weight, resolve_unit};
+use axum::{extract::Query, http::StatusCode, Extension, Json};
+use chrono::NaiveDate;
+use log::{error, warn};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug)]
+pub struct ScoresQuery {
+    pub unit: Option<WeightUnit>,
+    pub sex: Option<Sex>,
+    pub squat: Option<String>,
+    pub bench: Option<String>,
+    pub deadlift: Option<String>,
+    // Use estimated one rep maxes instead of the heaviest weight actually lifted
+    #[serde(default)]
+    pub estimated: bool,
+    pub include_types: Option<String>,
+    pub exclude_types: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ScorePoint {
+    pub date: NaiveDate,
+    pub bodyweight: f64,
+    pub squat: f64,
+    pub bench: f64,
+    pub deadlift: f64,
+    pub total: f64,
+    pub relative_squat: f64,
+    pub relative_bench: f64,
+    pub relative_deadlift: f64,
+    pub wilks: f64,
+    pub wilks2: f64,
+    pub dots: f64,
+    pub ipf_gl: f64,
+}
+
+#[derive(Serialize)]
+pub struct ResponseScores {
+    pub unit: WeightUnit,
+    pub sex: Sex,
+    pub points: Vec<ScorePoint>,
+}
+
+#[derive(Default)]
+struct BestLifts {
+    squat: f64,
+    bench: f64,
+    deadlift: f64,
+}
+
+fn matches_exercise(exercise_name: &str, wanted: &str) -> bool {
+    exercise_name.trim().eq_ignore_ascii_case(wanted.trim())
+}
+
+pub async fn get_scores(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(query): Query<ScoresQuery>,
+) -> Result<Json<ResponseScores>, StatusCode> {
+    warn!("scores fetched by user: {}", user.username);
+
+    let unit = resolve_unit(query.unit, &user);
+    // Warm-ups never count towards a best lift unless explicitly asked for
+    let set_type_filter = SetTypeFilter::from_query(
+        query.include_types.as_deref(),
+        query.exclude_types.as_deref(),
+        &[SetType::WarmUp],
+    )?;
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+
+    let Some(sex) = query.sex.or(profile.sex) else {
+        warn!("scores requested without a sex in the query or profile");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let squat_name = query.squat.unwrap_or(String::from("Squat"));
+    let bench_name = query.bench.unwrap_or(String::from("Bench Press"));
+    let deadlift_name = query.deadlift.unwrap_or(String::from("Deadlift"));
+
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(&database)
+        .await
+        .map_err(|err| {
+            error!("error finding workout sets {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Bodyweight history in kg, oldest first
+    let bodyweights: Vec<(NaiveDate, f64)> = Measurements::find()
+        .filter(measurements::Column::UserId.eq(user.id))
+        .filter(measurements::Column::Kind.eq(MeasurementKind::Bodyweight))
+        .order_by_asc(measurements::Column::Date)
+        .all(&database)
+        .await
+        .map_err(|err| {
+            error!("error finding measurements {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(|measurement| {
+            (
+                local_date(&measurement.date, timezone),
+                convert_weight(measurement.value, measurement.unit, WeightUnit::Kg),
+            )
+        })
+        .collect();
+
+    let profile_bodyweight = profile
+        .bodyweight
+        .map(|bodyweight| convert_weight(bodyweight, profile.bodyweight_unit, WeightUnit::Kg));
+
+    // Latest weigh-in on or before the date, falling back to the profile and then the first weigh-in
+    let bodyweight_at = |date: NaiveDate| -> Option<f64> {
+        bodyweights
+            .iter()
+            .take_while(|(measured_on, _)| *measured_on <= date)
+            .last()
+            .map(|(_, bodyweight)| *bodyweight)
+            .or(profile_bodyweight)
+            .or(bodyweights.first().map(|(_, bodyweight)| *bodyweight))
+    };
+
+    let mut best = BestLifts::default();
+    let mut points: Vec<ScorePoint> = Vec::new();
+
+    for workout_set in workout_sets {
+        if workout_set.reps < 1 || !set_type_filter.includes(workout_set.set_type) {
+            continue;
+        }
+
+        let weight = convert_weight(workout_set.weight, workout_set.weight_unit, WeightUnit::Kg);
+        let lifted = if query.estimated {
+            estimated_one_rep_max(weight, workout_set.reps)
+        } else {
+            weight
+        };
+
+        let best_lift = if matches_exercise(&workout_set.exercise_name, &squat_name) {
+            &mut best.squat
+        } else if matches_exercise(&workout_set.exercise_name, &bench_name) {
+            &mut best.bench
+        } else if matches_exercise(&workout_set.exercise_name, &deadlift_name) {
+            &mut best.deadlift
+        } else {
+            continue;
+        };
+
+        if lifted <= *best_lift {
+            continue;
+        }
+        *best_lift = lifted;
+
+        if best.squat <= 0.0 || best.bench <= 0.0 || best.deadlift <= 0.0 {
+            continue;
+        }
+
+        let date = local_date(&workout_set.date, timezone);
+        let Some(bodyweight) = bodyweight_at(date) else {
+            continue;
+        };
+        let total = best.squat + best.bench + best.deadlift;
+
+        let point = ScorePoint {
+            date,
+            bodyweight: convert_weight(bodyweight, WeightUnit::Kg, unit),
+            squat: convert_weight(best.squat, WeightUnit::Kg, unit),
+            bench: convert_weight(best.bench, WeightUnit::Kg, unit),
+            deadlift: convert_weight(best.deadlift, WeightUnit::Kg, unit),
+            total: convert_weight(total, WeightUnit::Kg, unit),
+            relative_squat: best.squat / bodyweight,
+            relative_bench: best.bench / bodyweight,
+            relative_deadlift: best.deadlift / bodyweight,
+            wilks: wilks(sex, bodyweight, total),
+            wilks2: wilks2(sex, bodyweight, total),
+            dots: dots(sex, bodyweight, total),
+            ipf_gl: ipf_gl(sex, bodyweight, total),
+        };
+
+        // Several PRs on the same day collapse into that day's final numbers
+        match points.last_mut() {
+            Some(last) if last.date == date => *last = point,
+            _ => points.push(point),
+        }
+    }
+
+    Ok(Json(ResponseScores { unit, sex, points }))
+}
@@ -0,0 +1,323 @@
+use crate::database::sea_orm_active_enums::{SetType, WeightUnit};
+use crate::database::users::{self, Entity as Users, Model};
+use crate::database::{
+    stall_alerts, stall_alerts::Entity as StallAlerts, workout_sets,
+    workout_sets::Entity as WorkoutSets,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::routes::users::send_template_email;
+use crate::utils::email_templates::EmailTemplate;
+use crate::utils::stalls::{detect_stall, Stall, StrengthPoint};
+use crate::utils::strength::estimated_one_rep_max;
+use crate::utils::time::parse_timezone;
+use crate::utils::units::{convert_weight, resolve_unit};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::Utc;
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{
+    ActiveEnum, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::Duration;
+
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Deserialize, Debug)]
+pub struct StallsQuery {
+    pub unit: Option<WeightUnit>,
+    #[serde(default)]
+    pub include_dismissed: bool,
+}
+
+#[derive(Serialize)]
+pub struct ResponseStallAlert {
+    pub id: i32,
+    pub exercise_name: String,
+    pub best_one_rep_max: f64,
+    pub unit: WeightUnit,
+    pub best_at: DateTimeWithTimeZone,
+    pub last_trained_at: DateTimeWithTimeZone,
+    pub weeks_stalled: i32,
+    pub detected_at: DateTimeWithTimeZone,
+    pub dismissed: bool,
+}
+
+fn to_response(alert: stall_alerts::Model, unit: WeightUnit) -> ResponseStallAlert {
+    ResponseStallAlert {
+        id: alert.id,
+        exercise_name: alert.exercise_name,
+        best_one_rep_max: convert_weight(alert.best_one_rep_max_kg, WeightUnit::Kg, unit),
+        unit,
+        best_at: alert.best_at,
+        last_trained_at: alert.last_trained_at,
+        weeks_stalled: alert.weeks_stalled,
+        detected_at: alert.detected_at,
+        dismissed: alert.dismissed_at.is_some(),
+    }
+}
+
+// Runs detection for every user once a day for as long as the server is up
+pub fn spawn_stall_detection(database: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STALL_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let users = match Users::find()
+                .filter(users::Column::DeletedAt.is_null())
+                .all(&database)
+                .await
+            {
+                Ok(users) => users,
+                Err(err) => {
+                    error!("error finding users for stall detection {}", err);
+                    continue;
+                }
+            };
+
+            for user in users {
+                if detect_user_stalls(&database, &user).await.is_err() {
+                    error!("stall detection failed for user: {}", user.username);
+                }
+            }
+            warn!("stall detection finished");
+        }
+    });
+}
+
+async fn send_stall_email(
+    database: &DatabaseConnection,
+    user: &Model,
+    new_alerts: &[stall_alerts::Model],
+) -> Result<(), StatusCode> {
+    let profile = find_profile_or_default(database, user.id).await?;
+    if !profile.stall_alert_email || !user.is_email_verified || new_alerts.is_empty() {
+        return Ok(());
+    }
+
+    let alerts: Vec<Value> = new_alerts
+        .iter()
+        .map(|alert| {
+            json!({
+                "exercise_name": alert.exercise_name,
+                "best_one_rep_max": format!(
+                    "{:.1}",
+                    convert_weight(
+                        alert.best_one_rep_max_kg,
+                        WeightUnit::Kg,
+                        user.preferred_unit
+                    )
+                ),
+                "best_at": alert.best_at.date_naive().to_string(),
+                "weeks_stalled": alert.weeks_stalled,
+            })
+        })
+        .collect();
+
+    send_template_email(
+        EmailTemplate::StallAlerts,
+        profile.locale.as_deref(),
+        json!({
+            "name": profile.display_name.clone().unwrap_or(user.username.clone()),
+            "unit": user.preferred_unit.to_value(),
+            "alerts": alerts,
+        }),
+        user.username.clone(),
+    )
+    .await?;
+
+    let now: DateTimeWithTimeZone = Utc::now().into();
+    for alert in new_alerts {
+        let mut alert = alert.clone().into_active_model();
+        alert.emailed_at = Set(Some(now));
+        alert.update(database).await.map_err(|err| {
+            error!("error saving the stall alert {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    Ok(())
+}
+
+// Opens alerts for newly stalled exercises, refreshes open ones and resolves those that moved
+async fn detect_user_stalls(database: &DatabaseConnection, user: &Model) -> Result<(), StatusCode> {
+    let profile = find_profile_or_default(database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+    let now: DateTimeWithTimeZone = Utc::now().into();
+
+    let mut points: BTreeMap<String, Vec<StrengthPoint>> = BTreeMap::new();
+    let workout_sets = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::SetType.ne(SetType::WarmUp))
+        .filter(workout_sets::Column::Reps.gt(0))
+        .filter(workout_sets::Column::Weight.gt(0.0))
+        .order_by_asc(workout_sets::Column::Date)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    for workout_set in workout_sets {
+        let weight = convert_weight(workout_set.weight, workout_set.weight_unit, WeightUnit::Kg);
+        points
+            .entry(workout_set.exercise_name)
+            .or_default()
+            .push(StrengthPoint {
+                date: workout_set.date,
+                one_rep_max: estimated_one_rep_max(weight, workout_set.reps),
+            });
+    }
+
+    let mut open_alerts: HashMap<String, stall_alerts::Model> = StallAlerts::find()
+        .filter(stall_alerts::Column::UserId.eq(user.id))
+        .filter(stall_alerts::Column::ResolvedAt.is_null())
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|alert| (alert.exercise_name.clone(), alert))
+        .collect();
+
+    let exercise_names: BTreeSet<String> =
+        points.keys().chain(open_alerts.keys()).cloned().collect();
+
+    let mut new_alerts = Vec::new();
+    for exercise_name in exercise_names {
+        let stall: Option<Stall> = points.get(&exercise_name).and_then(|exercise_points| {
+            detect_stall(exercise_points, now, profile.stall_weeks as i64, timezone)
+        });
+
+        let result = match (stall, open_alerts.remove(&exercise_name)) {
+            (Some(stall), Some(alert)) => {
+                let mut alert = alert.into_active_model();
+                alert.best_one_rep_max_kg = Set(stall.best_one_rep_max);
+                alert.best_at = Set(stall.best_at);
+                alert.last_trained_at = Set(stall.last_trained_at);
+                alert.weeks_stalled = Set(stall.weeks_stalled as i32);
+                alert.update(database).await
+            }
+            (Some(stall), None) => {
+                let alert = stall_alerts::ActiveModel {
+                    exercise_name: Set(exercise_name),
+                    best_one_rep_max_kg: Set(stall.best_one_rep_max),
+                    best_at: Set(stall.best_at),
+                    last_trained_at: Set(stall.last_trained_at),
+                    weeks_stalled: Set(stall.weeks_stalled as i32),
+                    detected_at: Set(now),
+                    user_id: Set(Some(user.id)),
+                    ..Default::default()
+                }
+                .insert(database)
+                .await;
+                if let Ok(alert) = &alert {
+                    new_alerts.push(alert.clone());
+                }
+                alert
+            }
+            (None, Some(alert)) => {
+                let mut alert = alert.into_active_model();
+                alert.resolved_at = Set(Some(now));
+                alert.update(database).await
+            }
+            (None, None) => continue,
+        };
+
+        result.map_err(|err| {
+            error!("error saving the stall alert {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    // A failed email shouldn't undo the detection, the alerts are still visible in the app
+    if send_stall_email(database, user, &new_alerts).await.is_err() {
+        warn!("could not email stall alerts to user: {}", user.username);
+    }
+
+    Ok(())
+}
+
+async fn find_open_alerts(
+    database: &DatabaseConnection,
+    user: &Model,
+    stalls_query: &StallsQuery,
+) -> Result<Vec<ResponseStallAlert>, StatusCode> {
+    let unit = resolve_unit(stalls_query.unit, user);
+
+    let mut query = StallAlerts::find()
+        .filter(stall_alerts::Column::UserId.eq(user.id))
+        .filter(stall_alerts::Column::ResolvedAt.is_null());
+    if !stalls_query.include_dismissed {
+        query = query.filter(stall_alerts::Column::DismissedAt.is_null());
+    }
+
+    let alerts = query
+        .order_by_desc(stall_alerts::Column::WeeksStalled)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|alert| to_response(alert, unit))
+        .collect();
+
+    Ok(alerts)
+}
+
+pub async fn get_stall_alerts(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(stalls_query): Query<StallsQuery>,
+) -> Result<Json<Vec<ResponseStallAlert>>, StatusCode> {
+    warn!("stall alerts fetched by user: {}", user.username);
+
+    Ok(Json(
+        find_open_alerts(&database, &user, &stalls_query).await?,
+    ))
+}
+
+// Runs detection right away instead of waiting for the daily job
+pub async fn refresh_stall_alerts(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(stalls_query): Query<StallsQuery>,
+) -> Result<Json<Vec<ResponseStallAlert>>, StatusCode> {
+    warn!("stall alerts refreshed by user: {}", user.username);
+
+    detect_user_stalls(&database, &user).await?;
+
+    Ok(Json(
+        find_open_alerts(&database, &user, &stalls_query).await?,
+    ))
+}
+
+// Dismissed alerts stay quiet until the exercise improves and a later stall opens a new one
+pub async fn dismiss_stall_alert(
+    Extension(user): Extension<Model>,
+    Path(alert_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("stall alert dismissed by user: {}", user.username);
+
+    let alert = StallAlerts::find_by_id(alert_id)
+        .filter(stall_alerts::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut alert = alert.into_active_model();
+    alert.dismissed_at = Set(Some(Utc::now().into()));
+    alert.update(&database).await.map_err(|err| {
+        error!("error saving the stall alert {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
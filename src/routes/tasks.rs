@@ -0,0 +1,620 @@
+use crate::database::sea_orm_active_enums::{TaskRecurrence, WeekDay};
+use crate::database::users::Model;
+use crate::database::{
+    task_completions, task_completions::Entity as TaskCompletions, tasks, tasks::Entity as Tasks,
+    user_profiles,
+};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::recurrence::{occurrence_streak, RecurrenceRule};
+use crate::utils::streaks::Streak;
+use crate::utils::time::{local_date, parse_timezone};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{NaiveDate, Utc};
+use log::{error, warn};
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeSet, HashMap};
+
+const PRIORITIES: [&str; 3] = ["low", "medium", "high"];
+// How far from today a recurring task can start, either way
+const MAX_START_DAYS: i64 = 366;
+
+// Title, priority and recurrence of the checklist every user starts with
+const DEFAULT_TASKS: [(&str, &str, Option<TaskRecurrence>); 4] = [
+    ("Stretch", "medium", Some(TaskRecurrence::Daily)),
+    ("Take creatine", "low", Some(TaskRecurrence::Daily)),
+    ("Log bodyweight", "medium", Some(TaskRecurrence::Weekly)),
+    (
+        "Plan next week's training",
+        "high",
+        Some(TaskRecurrence::Weekly),
+    ),
+];
+
+#[derive(Deserialize, Debug)]
+pub struct RequestTask {
+    pub title: String,
+    pub priority: Option<String>,
+    pub description: Option<String>,
+    // One-off when missing
+    pub recurrence: Option<TaskRecurrence>,
+    // Every `interval` days or weeks, defaults to 1
+    pub interval: Option<i32>,
+    // Weekly tasks only, e.g. Monday and Thursday for twice a week
+    pub days: Option<Vec<WeekDay>>,
+    // Defaults to today
+    pub starts_on: Option<NaiveDate>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TasksQuery {
+    #[serde(default)]
+    pub include_deleted: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CompletionQuery {
+    // Any day within the occurrence, defaults to today
+    pub date: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseTask {
+    pub id: i32,
+    pub title: String,
+    pub priority: Option<String>,
+    pub description: Option<String>,
+    pub recurrence: Option<TaskRecurrence>,
+    pub interval: Option<i32>,
+    pub days: Vec<WeekDay>,
+    pub starts_on: Option<NaiveDate>,
+    pub rrule: Option<String>,
+    pub is_default: bool,
+    // Recurring tasks count as done only when their current occurrence is completed
+    pub is_completed: bool,
+    pub current_occurrence: Option<NaiveDate>,
+    pub streak: Option<Streak>,
+    pub completed_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseTaskCompletion {
+    pub occurrence_date: NaiveDate,
+    pub completed_at: DateTimeWithTimeZone,
+}
+
+#[derive(Serialize)]
+pub struct ResponseTaskHistory {
+    pub task_id: i32,
+    pub rrule: Option<String>,
+    pub streak: Option<Streak>,
+    // Completed share of the occurrences that are over, the open current one doesn't count
+    pub adherence_percent: Option<f64>,
+    pub completions: Vec<ResponseTaskCompletion>,
+    pub missed: Vec<NaiveDate>,
+}
+
+fn today(profile: &user_profiles::Model) -> NaiveDate {
+    local_date(&Utc::now().into(), parse_timezone(&profile.timezone))
+}
+
+// Tasks made recurring before they had a start date begin at their first completion
+fn recurrence_rule(
+    task: &tasks::Model,
+    profile: &user_profiles::Model,
+    completed: &BTreeSet<NaiveDate>,
+) -> Option<RecurrenceRule> {
+    Some(RecurrenceRule {
+        frequency: task.recurrence?,
+        interval: task.recurrence_interval.unwrap_or(1).max(1) as i64,
+        days: task
+            .recurrence_days
+            .clone()
+            .and_then(|days| serde_json::from_value(days).ok())
+            .unwrap_or_default(),
+        starts_on: task
+            .starts_on
+            .or(completed.first().copied())
+            .unwrap_or(today(profile)),
+        week_start: profile.week_start,
+    })
+}
+
+fn to_response(
+    task: tasks::Model,
+    profile: &user_profiles::Model,
+    completed: &BTreeSet<NaiveDate>,
+) -> ResponseTask {
+    let rule = recurrence_rule(&task, profile, completed);
+    let occurrences = rule
+        .as_ref()
+        .map(|rule| rule.occurrences_until(today(profile)))
+        .unwrap_or_default();
+    let current_occurrence = occurrences.last().copied();
+    let is_completed = match (&rule, current_occurrence) {
+        (None, _) => task.completed_at.is_some(),
+        (Some(_), Some(current_occurrence)) => completed.contains(&current_occurrence),
+        (Some(_), None) => false,
+    };
+
+    ResponseTask {
+        id: task.id,
+        title: task.title,
+        priority: task.priority,
+        description: task.description,
+        recurrence: task.recurrence,
+        interval: rule.as_ref().map(|rule| rule.interval as i32),
+        days: rule
+            .as_ref()
+            .map(|rule| rule.days.clone())
+            .unwrap_or_default(),
+        starts_on: rule.as_ref().map(|rule| rule.starts_on),
+        rrule: rule.as_ref().map(|rule| rule.to_rrule()),
+        is_default: task.is_default.unwrap_or(false),
+        is_completed,
+        current_occurrence,
+        streak: rule.map(|_| occurrence_streak(&occurrences, completed)),
+        completed_at: task.completed_at,
+        deleted_at: task.deleted_at,
+    }
+}
+
+fn validate_task(
+    request_task: &RequestTask,
+    today: NaiveDate,
+) -> Result<(String, Option<String>), StatusCode> {
+    let title = request_task.title.trim().to_string();
+    if title.is_empty() || title.len() > 100 {
+        warn!("invalid task title {}", title);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let priority = request_task
+        .priority
+        .as_ref()
+        .map(|priority| priority.trim().to_lowercase());
+    if priority
+        .as_deref()
+        .is_some_and(|priority| !PRIORITIES.contains(&priority))
+    {
+        warn!("invalid task priority {:?}", priority);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let is_valid_recurrence = match request_task.recurrence {
+        None => {
+            request_task.interval.is_none()
+                && request_task.days.is_none()
+                && request_task.starts_on.is_none()
+        }
+        Some(recurrence) => {
+            request_task
+                .interval
+                .is_none_or(|interval| (1..=52).contains(&interval))
+                && (recurrence == TaskRecurrence::Weekly
+                    || request_task.days.as_ref().is_none_or(Vec::is_empty))
+                && request_task
+                    .starts_on
+                    .is_none_or(|starts_on| (starts_on - today).num_days().abs() <= MAX_START_DAYS)
+        }
+    };
+    if !is_valid_recurrence {
+        warn!("invalid task recurrence {:?}", request_task);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok((title, priority))
+}
+
+// Days are kept in week order without duplicates so the rrule reads the same every time
+fn recurrence_days(days: &Option<Vec<WeekDay>>) -> Option<serde_json::Value> {
+    let days = days.as_ref().filter(|days| !days.is_empty())?;
+    let mut days = days.clone();
+    days.sort_by_key(|day| *day as i32);
+    days.dedup();
+    Some(json!(days))
+}
+
+// Called once when the user signs up
+pub async fn create_default_tasks(
+    database: &DatabaseConnection,
+    user_id: i32,
+    starts_on: NaiveDate,
+) -> Result<(), StatusCode> {
+    let default_tasks: Vec<tasks::ActiveModel> = DEFAULT_TASKS
+        .iter()
+        .map(|(title, priority, recurrence)| tasks::ActiveModel {
+            title: Set(title.to_string()),
+            priority: Set(Some(priority.to_string())),
+            is_default: Set(Some(true)),
+            recurrence: Set(*recurrence),
+            recurrence_interval: Set(recurrence.map(|_| 1)),
+            starts_on: Set(recurrence.map(|_| starts_on)),
+            user_id: Set(Some(user_id)),
+            ..Default::default()
+        })
+        .collect();
+
+    Tasks::insert_many(default_tasks)
+        .exec(database)
+        .await
+        .map_err(|err| {
+            error!("error saving the default tasks {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(())
+}
+
+// Deleted tasks are only found by restore
+async fn find_task(
+    database: &DatabaseConnection,
+    user_id: i32,
+    task_id: i32,
+) -> Result<tasks::Model, StatusCode> {
+    Tasks::find_by_id(task_id)
+        .filter(tasks::Column::UserId.eq(user_id))
+        .filter(tasks::Column::DeletedAt.is_null())
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Completion log of one task, or of every task of the user, oldest first
+async fn find_completions(
+    database: &DatabaseConnection,
+    user_id: i32,
+    task_id: Option<i32>,
+) -> Result<Vec<task_completions::Model>, StatusCode> {
+    let mut query = TaskCompletions::find().filter(task_completions::Column::UserId.eq(user_id));
+    if let Some(task_id) = task_id {
+        query = query.filter(task_completions::Column::TaskId.eq(task_id));
+    }
+
+    query
+        .order_by_asc(task_completions::Column::OccurrenceDate)
+        .all(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn completed_occurrences(completions: &[task_completions::Model]) -> BTreeSet<NaiveDate> {
+    completions
+        .iter()
+        .map(|completion| completion.occurrence_date)
+        .collect()
+}
+
+async fn save_task(
+    database: &DatabaseConnection,
+    task: tasks::ActiveModel,
+) -> Result<tasks::Model, StatusCode> {
+    task.update(database).await.map_err(|err| {
+        error!("error saving the task {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// The occurrence a completion applies to, future days and unscheduled days can't be completed
+fn find_occurrence(
+    rule: &RecurrenceRule,
+    date: NaiveDate,
+    today: NaiveDate,
+) -> Result<NaiveDate, StatusCode> {
+    if date > today {
+        warn!("cannot complete a task on a future date {}", date);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    rule.occurrence_for(date).ok_or_else(|| {
+        warn!("task isn't scheduled on {}", date);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+pub async fn get_all_tasks(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(tasks_query): Query<TasksQuery>,
+) -> Result<Json<Vec<ResponseTask>>, StatusCode> {
+    warn!("tasks fetched by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+
+    let mut completions: HashMap<i32, Vec<task_completions::Model>> = HashMap::new();
+    for completion in find_completions(&database, user.id, None).await? {
+        completions
+            .entry(completion.task_id)
+            .or_default()
+            .push(completion);
+    }
+
+    let mut query = Tasks::find().filter(tasks::Column::UserId.eq(user.id));
+    if !tasks_query.include_deleted {
+        query = query.filter(tasks::Column::DeletedAt.is_null());
+    }
+    let tasks = query
+        .order_by_asc(tasks::Column::Id)
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|task| {
+            let completed = completed_occurrences(
+                completions
+                    .get(&task.id)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            );
+            to_response(task, &profile, &completed)
+        })
+        .collect();
+
+    Ok(Json(tasks))
+}
+
+pub async fn get_one_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ResponseTask>, StatusCode> {
+    warn!("task fetched by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let task = find_task(&database, user.id, task_id).await?;
+    let completions = find_completions(&database, user.id, Some(task.id)).await?;
+
+    Ok(Json(to_response(
+        task,
+        &profile,
+        &completed_occurrences(&completions),
+    )))
+}
+
+// Every completed occurrence of a recurring task with its streak and the occurrences missed
+pub async fn get_task_history(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ResponseTaskHistory>, StatusCode> {
+    warn!("task history fetched by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let task = find_task(&database, user.id, task_id).await?;
+    let completions = find_completions(&database, user.id, Some(task.id)).await?;
+    let completed = completed_occurrences(&completions);
+
+    let rule = recurrence_rule(&task, &profile, &completed);
+    let occurrences = rule
+        .as_ref()
+        .map(|rule| rule.occurrences_until(today(&profile)))
+        .unwrap_or_default();
+    let is_current_open = occurrences
+        .last()
+        .is_some_and(|occurrence| !completed.contains(occurrence));
+    let past_occurrences = match is_current_open {
+        true => &occurrences[..occurrences.len() - 1],
+        false => &occurrences[..],
+    };
+    let missed: Vec<NaiveDate> = past_occurrences
+        .iter()
+        .filter(|occurrence| !completed.contains(occurrence))
+        .copied()
+        .collect();
+    let adherence_percent = (!past_occurrences.is_empty()).then(|| {
+        (past_occurrences.len() - missed.len()) as f64 / past_occurrences.len() as f64 * 100.0
+    });
+
+    Ok(Json(ResponseTaskHistory {
+        task_id: task.id,
+        rrule: rule.as_ref().map(|rule| rule.to_rrule()),
+        streak: rule.map(|_| occurrence_streak(&occurrences, &completed)),
+        adherence_percent,
+        completions: completions
+            .into_iter()
+            .map(|completion| ResponseTaskCompletion {
+                occurrence_date: completion.occurrence_date,
+                completed_at: completion.completed_at,
+            })
+            .collect(),
+        missed,
+    }))
+}
+
+pub async fn create_task(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_task): Json<RequestTask>,
+) -> Result<Json<i32>, StatusCode> {
+    warn!("task created by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let (title, priority) = validate_task(&request_task, today(&profile))?;
+    let is_recurring = request_task.recurrence.is_some();
+
+    let task = tasks::ActiveModel {
+        title: Set(title),
+        priority: Set(priority),
+        description: Set(request_task.description),
+        is_default: Set(Some(false)),
+        recurrence: Set(request_task.recurrence),
+        recurrence_interval: Set(is_recurring.then(|| request_task.interval.unwrap_or(1))),
+        recurrence_days: Set(recurrence_days(&request_task.days)),
+        starts_on: Set(is_recurring.then(|| request_task.starts_on.unwrap_or(today(&profile)))),
+        user_id: Set(Some(user.id)),
+        ..Default::default()
+    }
+    .insert(&database)
+    .await
+    .map_err(|err| {
+        error!("error saving the task {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(task.id))
+}
+
+// Changing the schedule keeps the completion log, occurrences that no longer exist just stop
+// counting towards the streak
+pub async fn update_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_task): Json<RequestTask>,
+) -> Result<(), StatusCode> {
+    warn!("task updated by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let (title, priority) = validate_task(&request_task, today(&profile))?;
+    let task = find_task(&database, user.id, task_id).await?;
+    let is_recurring = request_task.recurrence.is_some();
+    let starts_on = request_task.starts_on.or(task.starts_on);
+
+    let mut task = task.into_active_model();
+    task.title = Set(title);
+    task.priority = Set(priority);
+    task.description = Set(request_task.description);
+    task.recurrence = Set(request_task.recurrence);
+    task.recurrence_interval = Set(is_recurring.then(|| request_task.interval.unwrap_or(1)));
+    task.recurrence_days = Set(recurrence_days(&request_task.days));
+    task.starts_on = Set(is_recurring.then(|| starts_on.unwrap_or(today(&profile))));
+    save_task(&database, task).await?;
+
+    Ok(())
+}
+
+// Recurring tasks log the occurrence the date falls in, one-off tasks just get stamped
+pub async fn complete_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(completion_query): Query<CompletionQuery>,
+) -> Result<(), StatusCode> {
+    warn!("task completed by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let task = find_task(&database, user.id, task_id).await?;
+    let completions = find_completions(&database, user.id, Some(task.id)).await?;
+    let completed = completed_occurrences(&completions);
+    let now: DateTimeWithTimeZone = Utc::now().into();
+
+    if let Some(rule) = recurrence_rule(&task, &profile, &completed) {
+        let today = today(&profile);
+        let occurrence = find_occurrence(&rule, completion_query.date.unwrap_or(today), today)?;
+        // Completing an occurrence twice, or from two requests at once, keeps the first
+        TaskCompletions::insert(task_completions::ActiveModel {
+            task_id: Set(task.id),
+            occurrence_date: Set(occurrence),
+            completed_at: Set(now),
+            user_id: Set(Some(user.id)),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                task_completions::Column::TaskId,
+                task_completions::Column::OccurrenceDate,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(&database)
+        .await
+        .map_err(|err| {
+            error!("error saving the task completion {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    let mut task = task.into_active_model();
+    task.completed_at = Set(Some(now));
+    save_task(&database, task).await?;
+
+    Ok(())
+}
+
+pub async fn uncomplete_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(completion_query): Query<CompletionQuery>,
+) -> Result<(), StatusCode> {
+    warn!("task uncompleted by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let task = find_task(&database, user.id, task_id).await?;
+    let completions = find_completions(&database, user.id, Some(task.id)).await?;
+
+    // The task keeps the time of its latest remaining completion
+    let completed_at = match recurrence_rule(&task, &profile, &completed_occurrences(&completions))
+    {
+        Some(rule) => {
+            let today = today(&profile);
+            let occurrence = find_occurrence(&rule, completion_query.date.unwrap_or(today), today)?;
+            TaskCompletions::delete_many()
+                .filter(task_completions::Column::TaskId.eq(task.id))
+                .filter(task_completions::Column::OccurrenceDate.eq(occurrence))
+                .exec(&database)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            completions
+                .iter()
+                .filter(|completion| completion.occurrence_date != occurrence)
+                .map(|completion| completion.completed_at)
+                .max()
+        }
+        None => None,
+    };
+
+    let mut task = task.into_active_model();
+    task.completed_at = Set(completed_at);
+    save_task(&database, task).await?;
+
+    Ok(())
+}
+
+// Soft delete, the task can be brought back with restore
+pub async fn delete_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("task deleted by user: {}", user.username);
+
+    let mut task = find_task(&database, user.id, task_id)
+        .await?
+        .into_active_model();
+    task.deleted_at = Set(Some(Utc::now().into()));
+    save_task(&database, task).await?;
+
+    Ok(())
+}
+
+pub async fn restore_task(
+    Extension(user): Extension<Model>,
+    Path(task_id): Path<i32>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<(), StatusCode> {
+    warn!("task restored by user: {}", user.username);
+
+    let mut task = Tasks::find_by_id(task_id)
+        .filter(tasks::Column::UserId.eq(user.id))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?
+        .into_active_model();
+    task.deleted_at = Set(None);
+    save_task(&database, task).await?;
+
+    Ok(())
+}
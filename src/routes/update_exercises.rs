@@ -1,17 +1,28 @@
 use crate::database::users::Model;
-use crate::database::{exercises, exercises::Entity as Exercises, sea_orm_active_enums::Bodypart};
+use crate::database::{
+    exercises,
+    exercises::Entity as Exercises,
+    sea_orm_active_enums::{Equipment, TrackingType},
+};
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::routes::create_exercise::validate_progression;
 use axum::{extract::Path, http::StatusCode, Extension, Json};
 use sea_orm::ColumnTrait;
 use sea_orm::IntoActiveModel;
 use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, Set};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Serialize, Deserialize)]
 pub struct RequestExercise {
     pub id: Option<i32>,
     pub name: String,
-    pub bodypart: Bodypart,
+    pub bodypart: String,
     pub isfavorite: bool,
+    pub tracking_type: Option<TrackingType>,
+    pub equipment: Option<Equipment>,
+    pub progression_strategy: Option<String>,
+    pub progression_settings: Option<Value>,
 }
 
 pub async fn atomic_update_exercise(
@@ -20,15 +31,54 @@ pub async fn atomic_update_exercise(
     Extension(database): Extension<DatabaseConnection>,
     Json(request_exercise): Json<RequestExercise>,
 ) -> Result<(), StatusCode> {
+    let category_names = find_category_names(&database, user.id).await?;
+    let bodypart = resolve_category(&category_names, &request_exercise.bodypart)?;
     let user = user.into_active_model();
 
-    let update_exercise = exercises::ActiveModel {
+    let stored_exercise = Exercises::find_by_id(execrise_id)
+        .filter(exercises::Column::UserId.eq(user.id.clone().unwrap()))
+        .one(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Older clients only send the name, bodypart and favorite flag, the rest is left alone
+    let mut update_exercise = exercises::ActiveModel {
         id: Set(execrise_id),
         name: Set(request_exercise.name),
-        bodypart: Set(request_exercise.bodypart),
+        bodypart: Set(bodypart),
         isfavorite: Set(request_exercise.isfavorite),
         ..Default::default()
     };
+    if let Some(tracking_type) = request_exercise.tracking_type {
+        update_exercise.tracking_type = Set(tracking_type);
+    }
+    if let Some(equipment) = request_exercise.equipment {
+        update_exercise.equipment = Set(Some(equipment));
+    }
+    if request_exercise.progression_strategy.is_some()
+        || request_exercise.progression_settings.is_some()
+    {
+        // Stored settings only carry over while the strategy they were written for stays
+        let progression_settings = match &request_exercise.progression_strategy {
+            Some(strategy) if *strategy != stored_exercise.progression_strategy => {
+                request_exercise.progression_settings
+            }
+            _ => request_exercise
+                .progression_settings
+                .or(stored_exercise.progression_settings),
+        };
+        let progression_strategy = validate_progression(
+            Some(
+                request_exercise
+                    .progression_strategy
+                    .unwrap_or(stored_exercise.progression_strategy),
+            ),
+            progression_settings.as_ref(),
+        )?;
+        update_exercise.progression_strategy = Set(progression_strategy);
+        update_exercise.progression_settings = Set(progression_settings);
+    }
 
     Exercises::update(update_exercise)
         .filter(exercises::Column::UserId.eq(user.id.unwrap()))
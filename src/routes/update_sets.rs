@@ -1,19 +1,173 @@
 use crate::database::users::Model;
-use crate::database::{sea_orm_active_enums::Bodypart, workout_sets, workout_sets::Entity as Sets};
+use crate::database::{
+    sea_orm_active_enums::{SetGroupType, SetType, WeightUnit},
+    workout_sets,
+    workout_sets::Entity as Sets,
+};
+use crate::routes::categories::{find_category_names, resolve_category};
+use crate::routes::create_workout_set::{
+    find_finish_times, find_tracking_types, resolve_timing, validate_set, SetValues,
+};
+use crate::routes::goals::{complete_reached_goals, SET_GOAL_KINDS};
+use crate::utils::nullable;
+use crate::utils::set_validation::{resolve_group_type, resolve_pace, validate_group_types};
 use axum::{extract::Path, http::StatusCode, Extension, Json};
 use log::warn;
-use sea_orm::{ColumnTrait, IntoActiveModel, TransactionTrait};
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{prelude::DateTimeWithTimeZone, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, IntoActiveModel, Iterable, ModelTrait, TransactionTrait,
+};
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct RequestWorkoutSet {
     pub id: Option<i32>,
     pub exercise_name: String,
-    pub category: Bodypart,
-    pub reps: i32,
-    pub weight: f64,
+    pub category: String,
+    // Fields left out keep their stored value, so clients that don't know about one don't wipe
+    // it on every edit. Nullable fields are cleared by sending null. The comment is the
+    // exception, it's always written and a missing one clears it too.
+    pub reps: Option<i32>,
+    pub weight: Option<f64>,
+    pub unit: Option<WeightUnit>,
+    pub set_type: Option<SetType>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub rpe: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub rir: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub duration_seconds: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub distance_meters: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub pace_seconds_per_km: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub avg_heart_rate: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub max_heart_rate: Option<Option<i32>>,
     pub comment: Option<String>,
+    // Position within the workout and the superset or circuit the set belongs to
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub order_index: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub group_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub group_type: Option<Option<SetGroupType>>,
+    pub started_at: Option<DateTimeWithTimeZone>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub rest_seconds: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub time_under_tension_seconds: Option<Option<i32>>,
+}
+
+async fn find_set(
+    database: &DatabaseConnection,
+    user_id: i32,
+    set_id: i32,
+) -> Result<workout_sets::Model, StatusCode> {
+    Sets::find_by_id(set_id)
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+// Finish times of the sets around a stored one, out of finish times loaded for its date
+fn neighbour_finish_times(
+    finish_times: &[(i32, DateTimeWithTimeZone)],
+    workout_set: &workout_sets::Model,
+) -> Vec<DateTimeWithTimeZone> {
+    finish_times
+        .iter()
+        .filter(|(id, _)| *id != workout_set.id)
+        .map(|(_, date)| *date)
+        .collect()
+}
+
+// The stored set with the request applied on top of it
+fn merge_request_set(
+    stored: &workout_sets::Model,
+    request_set: &RequestWorkoutSet,
+    category: String,
+    finish_times: &[DateTimeWithTimeZone],
+) -> Result<workout_sets::Model, StatusCode> {
+    let mut merged = stored.clone();
+    merged.exercise_name = request_set.exercise_name.clone();
+    merged.category = category;
+    merged.comment = request_set.comment.clone();
+    merged.reps = request_set.reps.unwrap_or(stored.reps);
+    merged.weight = request_set.weight.unwrap_or(stored.weight);
+    merged.weight_unit = request_set.unit.unwrap_or(stored.weight_unit);
+    merged.set_type = request_set.set_type.unwrap_or(stored.set_type);
+    merged.rpe = nullable::merge(&request_set.rpe, &stored.rpe);
+    merged.rir = nullable::merge(&request_set.rir, &stored.rir);
+    merged.duration_seconds =
+        nullable::merge(&request_set.duration_seconds, &stored.duration_seconds);
+    merged.distance_meters = nullable::merge(&request_set.distance_meters, &stored.distance_meters);
+    merged.avg_heart_rate = nullable::merge(&request_set.avg_heart_rate, &stored.avg_heart_rate);
+    merged.max_heart_rate = nullable::merge(&request_set.max_heart_rate, &stored.max_heart_rate);
+
+    // A pace sent as null is worked out again from the duration and distance
+    if request_set.pace_seconds_per_km.is_some()
+        || request_set.duration_seconds.is_some()
+        || request_set.distance_meters.is_some()
+    {
+        merged.pace_seconds_per_km = resolve_pace(
+            request_set.pace_seconds_per_km.flatten(),
+            merged.duration_seconds,
+            merged.distance_meters,
+        );
+    }
+
+    // A new group id starts from the request's type rather than the old group's
+    let group_type = match request_set.group_id {
+        Some(_) => request_set.group_type.flatten(),
+        None => nullable::merge(&request_set.group_type, &stored.group_type),
+    };
+    merged.order_index = nullable::merge(&request_set.order_index, &stored.order_index);
+    merged.group_id = nullable::merge(&request_set.group_id, &stored.group_id);
+    merged.group_type =
+        resolve_group_type(merged.order_index, merged.group_id.as_deref(), group_type)?;
+
+    // Updates don't move a set in time, so a start time is measured against its logged finish.
+    // Rest is only worked out for the set being written, the sets after it keep the rest they
+    // were logged with.
+    (merged.rest_seconds, merged.time_under_tension_seconds) = match request_set.started_at {
+        Some(started_at) => resolve_timing(
+            finish_times,
+            stored.date,
+            Some(started_at),
+            request_set.rest_seconds.flatten(),
+            request_set.time_under_tension_seconds.flatten(),
+            merged.duration_seconds,
+        ),
+        None => (
+            nullable::merge(&request_set.rest_seconds, &stored.rest_seconds),
+            nullable::merge(
+                &request_set.time_under_tension_seconds,
+                &stored.time_under_tension_seconds,
+            ),
+        ),
+    };
+
+    Ok(merged)
+}
+
+// Only the columns that differ from the stored set are written
+fn to_update(
+    stored: workout_sets::Model,
+    merged: &workout_sets::Model,
+) -> workout_sets::ActiveModel {
+    let mut update_set = stored.clone().into_active_model();
+    for column in workout_sets::Column::iter() {
+        let value = merged.get(column);
+        if value != stored.get(column) {
+            update_set.set(column, value);
+        }
+    }
+    update_set
 }
 
 pub async fn atomic_update_set(
@@ -23,25 +177,38 @@ pub async fn atomic_update_set(
     Json(request_set): Json<RequestWorkoutSet>,
 ) -> Result<(), StatusCode> {
     warn!("set updated by user: {}", user.username);
-    let user = user.into_active_model();
-
-    let update_set = workout_sets::ActiveModel {
-        id: Set(set_id),
-        exercise_name: Set(request_set.exercise_name),
-        category: Set(request_set.category),
-        reps: Set(request_set.reps),
-        weight: Set(request_set.weight),
-        comment: Set(request_set.comment),
-        ..Default::default()
+    let tracking_types = find_tracking_types(&database, user.id).await?;
+    let category_names = find_category_names(&database, user.id).await?;
+    let category = resolve_category(&category_names, &request_set.category)?;
+
+    let stored = find_set(&database, user.id, set_id).await?;
+    // Only needed when the edit sends a start time
+    let finish_times = match request_set.started_at {
+        Some(_) => find_finish_times(&database, user.id, stored.date, stored.date).await?,
+        None => Vec::new(),
     };
+    let merged = merge_request_set(
+        &stored,
+        &request_set,
+        category,
+        &neighbour_finish_times(&finish_times, &stored),
+    )?;
+    validate_set(&tracking_types, &SetValues::from(&merged))?;
 
-    Sets::update(update_set)
-        .filter(workout_sets::Column::UserId.eq(user.id.unwrap()))
+    Sets::update(to_update(stored, &merged))
+        .filter(workout_sets::Column::UserId.eq(user.id))
         .filter(workout_sets::Column::Id.eq(set_id))
         .exec(&database)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if complete_reached_goals(&database, user.id, &SET_GOAL_KINDS)
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user.id);
+    }
+
     Ok(())
 }
 
@@ -50,32 +217,78 @@ pub async fn atomic_update_sets(
     Extension(database): Extension<DatabaseConnection>,
     Json(request_sets): Json<Vec<RequestWorkoutSet>>,
 ) -> Result<(), StatusCode> {
-    warn!("{} sets updated by user: {}", request_sets.len(), user.username);
+    warn!(
+        "{} sets updated by user: {}",
+        request_sets.len(),
+        user.username
+    );
     let user_id = user.into_active_model().id.unwrap();
+    let tracking_types = find_tracking_types(&database, user_id).await?;
+    let category_names = find_category_names(&database, user_id).await?;
+
+    let set_ids = request_sets
+        .iter()
+        .map(|request_set| request_set.id.ok_or(StatusCode::BAD_REQUEST))
+        .collect::<Result<Vec<i32>, StatusCode>>()?;
 
     let txn = database
         .begin()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Every stored set in one query, and the finish times around the ones that get a start
+    // time in another
+    let stored_sets: HashMap<i32, workout_sets::Model> = Sets::find()
+        .filter(workout_sets::Column::UserId.eq(user_id))
+        .filter(workout_sets::Column::Id.is_in(set_ids.clone()))
+        .all(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|workout_set| (workout_set.id, workout_set))
+        .collect();
+
+    let timed_dates: Vec<DateTimeWithTimeZone> = request_sets
+        .iter()
+        .zip(&set_ids)
+        .filter(|(request_set, _)| request_set.started_at.is_some())
+        .filter_map(|(_, set_id)| stored_sets.get(set_id).map(|stored| stored.date))
+        .collect();
+    let finish_times = match (timed_dates.iter().min(), timed_dates.iter().max()) {
+        (Some(earliest), Some(latest)) => {
+            find_finish_times(&txn, user_id, *earliest, *latest).await?
+        }
+        _ => Vec::new(),
+    };
+
+    let mut updates = Vec::new();
+    for (request_set, set_id) in request_sets.iter().zip(set_ids) {
+        let category = resolve_category(&category_names, &request_set.category)?;
+        let stored = stored_sets
+            .get(&set_id)
+            .cloned()
+            .ok_or(StatusCode::NOT_FOUND)?;
+        let merged = merge_request_set(
+            &stored,
+            request_set,
+            category,
+            &neighbour_finish_times(&finish_times, &stored),
+        )?;
+        validate_set(&tracking_types, &SetValues::from(&merged))?;
+        updates.push((stored, merged));
+    }
+    validate_group_types(
+        updates
+            .iter()
+            .filter_map(|(_, merged)| Some((merged.group_id.as_deref()?, merged.group_type?))),
+    )?;
+
     // Loop through the request_sets and update each set in the database
-    for request_set in request_sets.iter() {
-        let set_id = request_set.id.unwrap();
-
-        let update_set = workout_sets::ActiveModel {
-            id: Set(set_id),
-            exercise_name: Set(request_set.exercise_name.clone()),
-            category: Set(request_set.category.clone()),
-            reps: Set(request_set.reps),
-            weight: Set(request_set.weight),
-            comment: Set(request_set.comment.clone()),
-            ..Default::default()
-        };
-
-        Sets::update(update_set)
+    for (stored, merged) in updates {
+        Sets::update(to_update(stored, &merged))
             .filter(workout_sets::Column::UserId.eq(user_id))
-            .filter(workout_sets::Column::Id.eq(set_id))
-            .exec(&database)
+            .filter(workout_sets::Column::Id.eq(merged.id))
+            .exec(&txn)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     }
@@ -84,5 +297,91 @@ pub async fn atomic_update_sets(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    if complete_reached_goals(&database, user_id, &SET_GOAL_KINDS)
+        .await
+        .is_err()
+    {
+        warn!("could not check goals for user: {}", user_id);
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::prelude::ChronoDateTime;
+    use serde_json::json;
+
+    fn stored_set() -> workout_sets::Model {
+        workout_sets::Model {
+            id: 1,
+            date: ChronoDateTime::default().and_utc().fixed_offset(),
+            exercise_name: "Bench Press".to_string(),
+            category: "Chest".to_string(),
+            reps: 5,
+            weight: 100.0,
+            weight_unit: WeightUnit::Kg,
+            set_type: SetType::Working,
+            rpe: Some(8.0),
+            rir: Some(2),
+            duration_seconds: None,
+            distance_meters: None,
+            pace_seconds_per_km: None,
+            avg_heart_rate: None,
+            max_heart_rate: None,
+            comment: None,
+            order_index: Some(0),
+            group_id: Some("a".to_string()),
+            group_type: Some(SetGroupType::Superset),
+            rest_seconds: Some(90),
+            time_under_tension_seconds: None,
+            user_id: Some(1),
+        }
+    }
+
+    fn merge(request: serde_json::Value) -> workout_sets::Model {
+        let request_set: RequestWorkoutSet = serde_json::from_value(request).unwrap();
+        merge_request_set(&stored_set(), &request_set, "Chest".to_string(), &[]).unwrap()
+    }
+
+    #[test]
+    fn missing_fields_keep_their_stored_value() {
+        let merged = merge(json!({ "exercise_name": "Bench Press", "category": "Chest" }));
+        assert_eq!(merged.rpe, Some(8.0));
+        assert_eq!(merged.rest_seconds, Some(90));
+        assert_eq!(merged.group_id.as_deref(), Some("a"));
+        assert_eq!(merged.group_type, Some(SetGroupType::Superset));
+    }
+
+    #[test]
+    fn sent_fields_replace_their_stored_value() {
+        let merged = merge(json!({
+            "exercise_name": "Bench Press",
+            "category": "Chest",
+            "rpe": 9.5,
+            "rest_seconds": 120,
+            "group_id": "b",
+        }));
+        assert_eq!(merged.rpe, Some(9.5));
+        assert_eq!(merged.rest_seconds, Some(120));
+        assert_eq!(merged.group_id.as_deref(), Some("b"));
+        assert_eq!(merged.rir, Some(2));
+    }
+
+    #[test]
+    fn null_fields_clear_their_stored_value() {
+        let merged = merge(json!({
+            "exercise_name": "Bench Press",
+            "category": "Chest",
+            "rpe": null,
+            "rest_seconds": null,
+            "group_id": null,
+        }));
+        assert_eq!(merged.rpe, None);
+        assert_eq!(merged.rest_seconds, None);
+        assert_eq!(merged.group_id, None);
+        assert_eq!(merged.group_type, None);
+        assert_eq!(merged.rir, Some(2));
+    }
+}
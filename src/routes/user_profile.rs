@@ -0,0 +1,263 @@
+use crate::database::sea_orm_active_enums::{Sex, WeekDay, WeightUnit};
+use crate::database::users::Model;
+use crate::database::{user_profiles, user_profiles::Entity as UserProfiles};
+use crate::utils::email_templates::{is_supported_locale, DEFAULT_LOCALE};
+use crate::utils::nullable;
+use crate::utils::units::convert_weight;
+use axum::{http::StatusCode, Extension, Json};
+use chrono::{Datelike, Utc};
+use chrono_tz::Tz;
+use log::{error, warn};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel, NotSet,
+    QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Debug)]
+pub struct ResponseProfile {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub timezone: String,
+    pub preferred_unit: WeightUnit,
+    pub week_start: WeekDay,
+    pub bodyweight: Option<f64>,
+    pub birth_year: Option<i32>,
+    pub sex: Option<Sex>,
+    pub is_public: bool,
+    pub share_bodyweight: bool,
+    pub stall_weeks: i32,
+    pub stall_alert_email: bool,
+    pub locale: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RequestProfile {
+    pub display_name: Option<String>,
+    pub timezone: Option<String>,
+    pub preferred_unit: Option<WeightUnit>,
+    pub week_start: Option<WeekDay>,
+    // Sending null clears these, leaving them out keeps them
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub bodyweight: Option<Option<f64>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub birth_year: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    pub sex: Option<Option<Sex>>,
+    pub is_public: Option<bool>,
+    pub share_bodyweight: Option<bool>,
+    pub stall_weeks: Option<i32>,
+    pub stall_alert_email: Option<bool>,
+    pub locale: Option<String>,
+}
+
+pub async fn find_profile(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<Option<user_profiles::Model>, StatusCode> {
+    UserProfiles::find()
+        .filter(user_profiles::Column::UserId.eq(user_id))
+        .one(database)
+        .await
+        .map_err(|err| {
+            error!("error finding the profile {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+// What a user without a stored profile gets, profiles are created lazily on the first update
+// so existing users don't need a backfill
+fn default_profile(user_id: i32) -> user_profiles::Model {
+    user_profiles::Model {
+        id: 0,
+        user_id,
+        display_name: None,
+        timezone: String::from("UTC"),
+        week_start: WeekDay::Monday,
+        bodyweight: None,
+        bodyweight_unit: WeightUnit::Kg,
+        birth_year: None,
+        sex: None,
+        is_public: false,
+        share_bodyweight: false,
+        stall_weeks: 4,
+        stall_alert_email: false,
+        locale: None,
+    }
+}
+
+// For reads, nothing is stored until the user changes a setting
+pub async fn find_profile_or_default(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<user_profiles::Model, StatusCode> {
+    Ok(find_profile(database, user_id)
+        .await?
+        .unwrap_or_else(|| default_profile(user_id)))
+}
+
+async fn get_or_create_profile(
+    database: &DatabaseConnection,
+    user_id: i32,
+) -> Result<user_profiles::Model, StatusCode> {
+    if let Some(profile) = find_profile(database, user_id).await? {
+        return Ok(profile);
+    }
+
+    // Two first requests may race here, whichever insert loses leaves the other's row alone
+    let new_profile = user_profiles::ActiveModel {
+        id: NotSet,
+        ..default_profile(user_id).into_active_model()
+    };
+    UserProfiles::insert(new_profile)
+        .on_conflict(
+            OnConflict::column(user_profiles::Column::UserId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(database)
+        .await
+        .map_err(|err| {
+            error!("error saving the new profile {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    find_profile(database, user_id)
+        .await?
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn to_response(user: &Model, profile: user_profiles::Model) -> ResponseProfile {
+    ResponseProfile {
+        username: user.username.clone(),
+        display_name: profile.display_name,
+        timezone: profile.timezone,
+        preferred_unit: user.preferred_unit,
+        week_start: profile.week_start,
+        bodyweight: profile.bodyweight.map(|bodyweight| {
+            convert_weight(bodyweight, profile.bodyweight_unit, user.preferred_unit)
+        }),
+        birth_year: profile.birth_year,
+        sex: profile.sex,
+        is_public: profile.is_public,
+        share_bodyweight: profile.share_bodyweight,
+        stall_weeks: profile.stall_weeks,
+        stall_alert_email: profile.stall_alert_email,
+        locale: profile
+            .locale
+            .unwrap_or_else(|| String::from(DEFAULT_LOCALE)),
+    }
+}
+
+pub async fn get_profile(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+) -> Result<Json<ResponseProfile>, StatusCode> {
+    warn!("profile fetched by user: {}", user.username);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+
+    Ok(Json(to_response(&user, profile)))
+}
+
+pub async fn update_profile(
+    Extension(user): Extension<Model>,
+    Extension(database): Extension<DatabaseConnection>,
+    Json(request_profile): Json<RequestProfile>,
+) -> Result<Json<ResponseProfile>, StatusCode> {
+    warn!("profile updated by user: {}", user.username);
+
+    if let Some(timezone) = &request_profile.timezone {
+        if timezone.parse::<Tz>().is_err() {
+            warn!("invalid timezone {}", timezone);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(Some(birth_year)) = request_profile.birth_year {
+        if birth_year < 1900 || birth_year > Utc::now().year() {
+            warn!("invalid birth year {}", birth_year);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(Some(bodyweight)) = request_profile.bodyweight {
+        if bodyweight <= 0.0 {
+            warn!("invalid bodyweight {}", bodyweight);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(stall_weeks) = request_profile.stall_weeks {
+        if !(1..=52).contains(&stall_weeks) {
+            warn!("invalid stall weeks {}", stall_weeks);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(locale) = &request_profile.locale {
+        if !is_supported_locale(locale) {
+            warn!("unsupported locale {}", locale);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let mut user = user;
+    if let Some(preferred_unit) = request_profile.preferred_unit {
+        let mut active_user = user.clone().into_active_model();
+        active_user.preferred_unit = Set(preferred_unit);
+        user = active_user.update(&database).await.map_err(|err| {
+            error!("error saving user {}", err);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    }
+
+    let mut profile = get_or_create_profile(&database, user.id)
+        .await?
+        .into_active_model();
+
+    if let Some(display_name) = request_profile.display_name {
+        // An empty display name clears it
+        let display_name = display_name.trim().to_string();
+        profile.display_name = Set((!display_name.is_empty()).then_some(display_name));
+    }
+    if let Some(timezone) = request_profile.timezone {
+        profile.timezone = Set(timezone);
+    }
+    if let Some(week_start) = request_profile.week_start {
+        profile.week_start = Set(week_start);
+    }
+    if let Some(bodyweight) = request_profile.bodyweight {
+        profile.bodyweight = Set(bodyweight);
+        profile.bodyweight_unit = Set(user.preferred_unit);
+    }
+    if let Some(birth_year) = request_profile.birth_year {
+        profile.birth_year = Set(birth_year);
+    }
+    if let Some(sex) = request_profile.sex {
+        profile.sex = Set(sex);
+    }
+    if let Some(is_public) = request_profile.is_public {
+        profile.is_public = Set(is_public);
+    }
+    if let Some(share_bodyweight) = request_profile.share_bodyweight {
+        profile.share_bodyweight = Set(share_bodyweight);
+    }
+    if let Some(stall_weeks) = request_profile.stall_weeks {
+        profile.stall_weeks = Set(stall_weeks);
+    }
+    if let Some(stall_alert_email) = request_profile.stall_alert_email {
+        profile.stall_alert_email = Set(stall_alert_email);
+    }
+    if let Some(locale) = request_profile.locale {
+        profile.locale = Set(Some(locale));
+    }
+
+    let profile = profile.update(&database).await.map_err(|err| {
+        error!("error saving the profile {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(to_response(&user, profile)))
+}
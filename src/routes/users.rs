@@ -2,15 +2,20 @@ use std::convert::Infallible;
 
 use crate::database;
 // use crate::database::users::Entity as Users;
+use crate::database::sea_orm_active_enums::WeightUnit;
 use crate::database::users::Model;
 use crate::database::{users, users::Entity as Users};
+use crate::routes::tasks::create_default_tasks;
+use crate::routes::user_profile::find_profile;
+use crate::utils::email_templates::{render_email, EmailTemplate, RenderedEmail};
 use crate::utils::jwt::create_jwt;
 use crate::utils::jwt::is_valid;
 use axum::extract::Query;
 use axum::http::Response;
 use axum::{http::StatusCode, Extension, Json};
+use chrono::Utc;
 use dotenvy_macro::dotenv;
-use lettre::message::header::ContentType;
+use lettre::message::MultiPart;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{Message, SmtpTransport, Transport};
 use log::{error, warn};
@@ -21,6 +26,7 @@ use sea_orm::QueryFilter;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
 use sea_orm::{ColumnTrait, DbErr};
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -59,6 +65,11 @@ pub struct RequestEmailVerificationUser {
     username: String,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RequestPreferredUnit {
+    unit: WeightUnit,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct QueryParams {
     username: String,
@@ -136,14 +147,23 @@ pub async fn create_user(
 
     warn!("create user sucessful");
 
-    if let Ok(()) = send_email(
-        String::from("Verifit: Email Verification"),
-        format!(
-            "Dear {},\nTo verify your email click on the following link:\n https://verifit.xyz/users/verify-email?username={}&token={}",
-            request_user.username.to_string(),
-            request_user.username.to_string(),
-            email_verification_jwt,
-        ),
+    // New users have no timezone yet, the recurring defaults start on the UTC date
+    let user_id = new_user.id.clone().unwrap();
+    if create_default_tasks(&database, user_id, Utc::now().date_naive())
+        .await
+        .is_err()
+    {
+        warn!("could not create the default tasks for user: {}", user_id);
+    }
+
+    // New users have no profile yet so they get the default locale
+    if let Ok(()) = send_template_email(
+        EmailTemplate::VerifyEmail,
+        None,
+        json!({
+            "name": request_user.username,
+            "verify_url": verify_email_url(&request_user.username, &email_verification_jwt),
+        }),
         request_user.username.to_string(),
     )
     .await
@@ -236,6 +256,27 @@ pub async fn logout(
     Ok(())
 }
 
+pub async fn update_preferred_unit(
+    Extension(database): Extension<DatabaseConnection>,
+    Extension(user): Extension<Model>,
+    Json(request_unit): Json<RequestPreferredUnit>,
+) -> Result<(), StatusCode> {
+    warn!(
+        "preferred unit changed to {:?} by user: {}",
+        request_unit.unit, user.username
+    );
+
+    let mut user = user.into_active_model();
+
+    user.preferred_unit = Set(request_unit.unit);
+    user.save(&database).await.map_err(|err| {
+        error!("error saving user {}", err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(())
+}
+
 pub async fn request_password_reset(
     Extension(database): Extension<DatabaseConnection>,
     Json(password_reset_user): Json<RequestPasswordResetUser>,
@@ -274,6 +315,12 @@ pub async fn request_password_reset(
     user.reset_code = Set(Some(new_reset_token.clone()));
 
     let recipient_email = user.username.clone().unwrap();
+    // Nobody is signed in here, so a missing profile isn't created
+    let profile = find_profile(&database, user.id.clone().unwrap()).await?;
+    let locale = profile.as_ref().and_then(|profile| profile.locale.clone());
+    let name = profile
+        .and_then(|profile| profile.display_name)
+        .unwrap_or(recipient_email.clone());
 
     user.save(&database).await.map_err(|err| {
         error!("error saving the new user: {}", err);
@@ -281,9 +328,13 @@ pub async fn request_password_reset(
     })?;
 
     // Send the recovery email
-    if let Ok(()) = send_email(
-        String::from("Verifit: Password Reset Code"),
-        generate_reset_code(&new_reset_token),
+    if let Ok(()) = send_template_email(
+        EmailTemplate::PasswordReset,
+        locale.as_deref(),
+        json!({
+            "name": name,
+            "reset_code": generate_reset_code(&new_reset_token),
+        }),
         recipient_email,
     )
     .await
@@ -356,19 +407,40 @@ pub async fn change_password(
     Ok(())
 }
 
-async fn send_email(title: String, message: String, username: String) -> Result<(), StatusCode> {
+fn verify_email_url(username: &str, token: &str) -> String {
+    format!(
+        "https://verifit.xyz/users/verify-email?username={}&token={}",
+        username, token
+    )
+}
+
+// Renders the template in the user's locale and sends it, all outgoing mail goes through here
+pub async fn send_template_email(
+    template: EmailTemplate,
+    locale: Option<&str>,
+    context: Value,
+    username: String,
+) -> Result<(), StatusCode> {
+    let email = render_email(template, locale, &context).map_err(|err| {
+        error!("error rendering the {} email {}", template.name(), err);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    send_email(email, username).await
+}
+
+pub async fn send_email(email: RenderedEmail, username: String) -> Result<(), StatusCode> {
     let email_username: &'static str = dotenv!("EMAIL_USERNAME");
     let email_password: &'static str = dotenv!("EMAIL_PASSWORD");
     let smtp_server: &'static str = dotenv!("SMTP_SERVER");
 
     warn!("Sending to email: {}", username);
 
-    let email = Message::builder()
+    let message = Message::builder()
         .from(format!("<{}>", email_username).parse().unwrap())
         .to(format!("<{}>", username).parse().unwrap())
-        .subject(title)
-        .header(ContentType::TEXT_PLAIN)
-        .body(message)
+        .subject(email.subject)
+        .multipart(MultiPart::alternative_plain_html(email.text, email.html))
         .unwrap();
 
     let creds = Credentials::new(email_username.to_owned(), email_password.to_owned());
@@ -380,7 +452,7 @@ async fn send_email(title: String, message: String, username: String) -> Result<
         .build();
 
     // Send the email
-    match mailer.send(&email) {
+    match mailer.send(&message) {
         Ok(_) => {
             warn!("Email sent successfully!");
             Ok(())
@@ -476,6 +548,14 @@ pub async fn request_email_verification(
         None => return Err(StatusCode::NOT_FOUND),
     };
 
+    // Greet the user by their display name if they have set one. Nobody is signed in here, so
+    // a missing profile isn't created.
+    let profile = find_profile(&database, db_user.clone().unwrap().id).await?;
+    let locale = profile.as_ref().and_then(|profile| profile.locale.clone());
+    let greeting_name = profile
+        .and_then(|profile| profile.display_name)
+        .unwrap_or(username.clone());
+
     // Generate new email verification token
     let expiration_duration_email_token: &'static str = dotenv!("PASSWORD_RESET_EXPIRATION");
     let email_verification_jwt = create_jwt(expiration_duration_email_token)?;
@@ -488,15 +568,15 @@ pub async fn request_email_verification(
         StatusCode::INTERNAL_SERVER_ERROR
     });
 
-    if let Ok(()) = send_email(
-        String::from("Verifit: Email Verification"),
-        format!(
-            "Dear {},\nTo verify your email click on the following link:\n https://verifit.xyz/users/verify-email?username={}&token={}",
-            username,
-            username,
-            email_verification_jwt,
-        ),
-        username)
+    if let Ok(()) = send_template_email(
+        EmailTemplate::VerifyEmail,
+        locale.as_deref(),
+        json!({
+            "name": greeting_name,
+            "verify_url": verify_email_url(&username, &email_verification_jwt),
+        }),
+        username,
+    )
     .await
     {
         Ok(())
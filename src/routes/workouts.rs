@@ -0,0 +1,139 @@
+use crate::database::sea_orm_active_enums::SetGroupType;
+use crate::database::users::Model;
+use crate::database::{workout_sets, workout_sets::Entity as WorkoutSets};
+use crate::routes::get_workout_sets::{to_response, ResponseWorkoutSet};
+use crate::routes::user_profile::find_profile_or_default;
+use crate::utils::time::{local_date, parse_timezone};
+use crate::utils::units::{resolve_unit, UnitQuery};
+use axum::{
+    extract::{Path, Query},
+    http::StatusCode,
+    Extension, Json,
+};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use log::warn;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct WorkoutExercise {
+    // "A1", "A2" for a superset, "B1" for the straight sets that follow it
+    pub label: String,
+    pub exercise_name: String,
+    pub sets: Vec<ResponseWorkoutSet>,
+}
+
+#[derive(Serialize)]
+pub struct WorkoutBlock {
+    pub label: String,
+    pub group_id: Option<String>,
+    pub group_type: Option<SetGroupType>,
+    pub exercises: Vec<WorkoutExercise>,
+}
+
+#[derive(Serialize)]
+pub struct ResponseWorkout {
+    pub date: NaiveDate,
+    pub blocks: Vec<WorkoutBlock>,
+}
+
+// A..Z, then AA, AB and so on
+fn block_label(index: usize) -> String {
+    let letter = char::from(b'A' + (index % 26) as u8);
+    match index / 26 {
+        0 => letter.to_string(),
+        prefix => format!("{}{}", block_label(prefix - 1), letter),
+    }
+}
+
+// Sets with an explicit order come first in that order, the rest follow by time. Grouped sets
+// form one block per group, every other exercise gets a block of its own.
+fn to_blocks(mut workout_sets: Vec<ResponseWorkoutSet>) -> Vec<WorkoutBlock> {
+    workout_sets.sort_by_key(|workout_set| {
+        (
+            workout_set.order_index.is_none(),
+            workout_set.order_index,
+            workout_set.date,
+            workout_set.id,
+        )
+    });
+
+    let mut blocks: Vec<WorkoutBlock> = Vec::new();
+    for workout_set in workout_sets {
+        let block_index = blocks.iter().position(|block| match &workout_set.group_id {
+            Some(group_id) => block.group_id.as_ref() == Some(group_id),
+            None => {
+                block.group_id.is_none()
+                    && block.exercises[0].exercise_name == workout_set.exercise_name
+            }
+        });
+        let block = match block_index {
+            Some(block_index) => &mut blocks[block_index],
+            None => {
+                blocks.push(WorkoutBlock {
+                    label: block_label(blocks.len()),
+                    group_id: workout_set.group_id.clone(),
+                    group_type: workout_set.group_type,
+                    exercises: Vec::new(),
+                });
+                blocks.last_mut().unwrap()
+            }
+        };
+
+        match block
+            .exercises
+            .iter_mut()
+            .find(|exercise| exercise.exercise_name == workout_set.exercise_name)
+        {
+            Some(exercise) => exercise.sets.push(workout_set),
+            None => block.exercises.push(WorkoutExercise {
+                label: format!("{}{}", block.label, block.exercises.len() + 1),
+                exercise_name: workout_set.exercise_name.clone(),
+                sets: vec![workout_set],
+            }),
+        }
+    }
+
+    blocks
+}
+
+// Every set logged on one local day, in order and grouped the way it was performed
+pub async fn get_workout(
+    Extension(user): Extension<Model>,
+    Path(date): Path<NaiveDate>,
+    Extension(database): Extension<DatabaseConnection>,
+    Query(unit_query): Query<UnitQuery>,
+) -> Result<Json<ResponseWorkout>, StatusCode> {
+    warn!("workout fetched by user: {}", user.username);
+    let unit = resolve_unit(unit_query.unit, &user);
+
+    let profile = find_profile_or_default(&database, user.id).await?;
+    let timezone = parse_timezone(&profile.timezone);
+
+    // A day of slack on both sides covers every timezone, the exact cut happens on local dates
+    let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap());
+    let since: DateTimeWithTimeZone = (midnight - Duration::days(1)).into();
+    let until: DateTimeWithTimeZone = (midnight + Duration::days(2)).into();
+
+    let workout_sets: Vec<ResponseWorkoutSet> = WorkoutSets::find()
+        .filter(workout_sets::Column::UserId.eq(user.id))
+        .filter(workout_sets::Column::Date.gte(since))
+        .filter(workout_sets::Column::Date.lt(until))
+        .all(&database)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter(|workout_set| local_date(&workout_set.date, timezone) == date)
+        .map(|workout_set| to_response(workout_set, unit))
+        .collect();
+
+    if workout_sets.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(Json(ResponseWorkout {
+        date,
+        blocks: to_blocks(workout_sets),
+    }))
+}
@@ -0,0 +1,209 @@
+use crate::utils::search::normalize;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+// Rules live in the data file so new badges don't need handler changes. Keys are stable,
+// unlocks are stored against them.
+const BUILTIN_ACHIEVEMENTS: &str = include_str!("../../data/achievements.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AchievementRule {
+    // Distinct local days with at least one set
+    Workouts {
+        threshold: f64,
+    },
+    Sets {
+        threshold: f64,
+    },
+    Exercises {
+        threshold: f64,
+    },
+    // Weight times reps in kg over one local day
+    SessionTonnage {
+        threshold: f64,
+    },
+    // Longest run of consecutive weeks with training
+    WeeklyStreak {
+        threshold: f64,
+    },
+    // Heaviest single set of any of the exercises divided by bodyweight at the time
+    BodyweightLift {
+        exercise_names: Vec<String>,
+        threshold: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Achievement {
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub rule: AchievementRule,
+}
+
+// Everything the rules are checked against, built once per evaluation
+#[derive(Debug, Default)]
+pub struct AchievementStats {
+    pub workouts: usize,
+    pub sets: usize,
+    pub exercises: usize,
+    pub best_session_tonnage: f64,
+    pub longest_weekly_streak: i64,
+    // Keyed by normalized exercise name
+    pub best_bodyweight_ratios: HashMap<String, f64>,
+}
+
+// One logged set as far as the stats are concerned
+pub struct StatsSet {
+    pub day: NaiveDate,
+    pub week_start: NaiveDate,
+    // Normalized
+    pub exercise_name: String,
+    // Weight times reps in kg
+    pub tonnage: f64,
+    pub bodyweight_ratio: Option<f64>,
+}
+
+// Builds the stats up one set at a time, oldest first, so the set that first met a rule and
+// with it the time the achievement was earned is known
+#[derive(Debug, Default)]
+pub struct StatsBuilder {
+    pub stats: AchievementStats,
+    session_tonnage: HashMap<NaiveDate, f64>,
+    exercise_names: HashSet<String>,
+    last_week: Option<NaiveDate>,
+    weekly_streak: i64,
+}
+
+impl StatsBuilder {
+    pub fn add(&mut self, stats_set: StatsSet) {
+        let tonnage = self.session_tonnage.entry(stats_set.day).or_default();
+        *tonnage += stats_set.tonnage;
+        self.stats.best_session_tonnage = self.stats.best_session_tonnage.max(*tonnage);
+        self.stats.workouts = self.session_tonnage.len();
+        self.stats.sets += 1;
+
+        if let Some(ratio) = stats_set.bodyweight_ratio {
+            let best = self
+                .stats
+                .best_bodyweight_ratios
+                .entry(stats_set.exercise_name.clone())
+                .or_default();
+            *best = best.max(ratio);
+        }
+        self.exercise_names.insert(stats_set.exercise_name);
+        self.stats.exercises = self.exercise_names.len();
+
+        if self.last_week != Some(stats_set.week_start) {
+            let follows = self
+                .last_week
+                .is_some_and(|last_week| last_week + Duration::weeks(1) == stats_set.week_start);
+            self.weekly_streak = if follows { self.weekly_streak + 1 } else { 1 };
+            self.stats.longest_weekly_streak =
+                self.stats.longest_weekly_streak.max(self.weekly_streak);
+            self.last_week = Some(stats_set.week_start);
+        }
+    }
+}
+
+// Parsed once, `run` touches them before serving so a bad file stops the server at boot
+static PARSED_BUILTIN_ACHIEVEMENTS: LazyLock<Vec<Achievement>> = LazyLock::new(|| {
+    serde_json::from_str(BUILTIN_ACHIEVEMENTS)
+        .unwrap_or_else(|err| panic!("built-in achievements are invalid: {}", err))
+});
+
+pub fn builtin_achievements() -> &'static [Achievement] {
+    &PARSED_BUILTIN_ACHIEVEMENTS
+}
+
+impl AchievementRule {
+    pub fn threshold(&self) -> f64 {
+        match self {
+            AchievementRule::Workouts { threshold }
+            | AchievementRule::Sets { threshold }
+            | AchievementRule::Exercises { threshold }
+            | AchievementRule::SessionTonnage { threshold }
+            | AchievementRule::WeeklyStreak { threshold }
+            | AchievementRule::BodyweightLift { threshold, .. } => *threshold,
+        }
+    }
+
+    pub fn current(&self, stats: &AchievementStats) -> f64 {
+        match self {
+            AchievementRule::Workouts { .. } => stats.workouts as f64,
+            AchievementRule::Sets { .. } => stats.sets as f64,
+            AchievementRule::Exercises { .. } => stats.exercises as f64,
+            AchievementRule::SessionTonnage { .. } => stats.best_session_tonnage,
+            AchievementRule::WeeklyStreak { .. } => stats.longest_weekly_streak as f64,
+            AchievementRule::BodyweightLift { exercise_names, .. } => exercise_names
+                .iter()
+                .filter_map(|exercise_name| {
+                    stats.best_bodyweight_ratios.get(&normalize(exercise_name))
+                })
+                .copied()
+                .fold(0.0, f64::max),
+        }
+    }
+
+    pub fn is_met(&self, stats: &AchievementStats) -> bool {
+        self.current(stats) >= self.threshold()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+
+    fn stats_set(day: &str, exercise_name: &str, tonnage: f64) -> StatsSet {
+        let day: NaiveDate = day.parse().unwrap();
+        StatsSet {
+            day,
+            week_start: day - Duration::days(day.weekday().num_days_from_monday() as i64),
+            exercise_name: exercise_name.to_string(),
+            tonnage,
+            bodyweight_ratio: None,
+        }
+    }
+
+    #[test]
+    fn sessions_are_totalled_per_day() {
+        let mut builder = StatsBuilder::default();
+        builder.add(stats_set("2024-01-01", "squat", 600.0));
+        builder.add(stats_set("2024-01-01", "bench press", 500.0));
+        builder.add(stats_set("2024-01-03", "squat", 800.0));
+        assert_eq!(builder.stats.workouts, 2);
+        assert_eq!(builder.stats.sets, 3);
+        assert_eq!(builder.stats.exercises, 2);
+        assert_eq!(builder.stats.best_session_tonnage, 1100.0);
+    }
+
+    #[test]
+    fn weekly_streaks_break_on_a_missed_week() {
+        let mut builder = StatsBuilder::default();
+        for day in [
+            "2024-01-01",
+            "2024-01-09",
+            "2024-01-17",
+            "2024-02-05",
+            "2024-02-12",
+        ] {
+            builder.add(stats_set(day, "squat", 0.0));
+        }
+        assert_eq!(builder.stats.longest_weekly_streak, 3);
+    }
+
+    #[test]
+    fn rules_are_met_as_the_stats_build_up() {
+        let rule = AchievementRule::Workouts { threshold: 2.0 };
+        let mut builder = StatsBuilder::default();
+        builder.add(stats_set("2024-01-01", "squat", 0.0));
+        builder.add(stats_set("2024-01-01", "squat", 0.0));
+        assert!(!rule.is_met(&builder.stats));
+        builder.add(stats_set("2024-01-02", "squat", 0.0));
+        assert!(rule.is_met(&builder.stats));
+    }
+}
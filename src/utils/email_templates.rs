@@ -0,0 +1,323 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// Each locale has a directory per template holding subject.txt, body.html and body.txt, and the
+// shared layout.html and layout.txt wrap every body in the branding. The templates are built
+// into the binary, files in EMAIL_TEMPLATES_DIR take their place when it's set so copy changes
+// can be tried without a rebuild.
+macro_rules! embedded {
+    ($($path:literal),* $(,)?) => {
+        &[$(($path, include_str!(concat!("../../templates/email/", $path)))),*]
+    };
+}
+
+static EMBEDDED_TEMPLATES: &[(&str, &str)] = embedded![
+    "layout.html",
+    "layout.txt",
+    "en/footer.txt",
+    "en/verify_email/subject.txt",
+    "en/verify_email/body.html",
+    "en/verify_email/body.txt",
+    "en/password_reset/subject.txt",
+    "en/password_reset/body.html",
+    "en/password_reset/body.txt",
+    "en/stall_alerts/subject.txt",
+    "en/stall_alerts/body.html",
+    "en/stall_alerts/body.txt",
+    "el/footer.txt",
+    "el/verify_email/subject.txt",
+    "el/verify_email/body.html",
+    "el/verify_email/body.txt",
+    "el/password_reset/subject.txt",
+    "el/password_reset/body.html",
+    "el/password_reset/body.txt",
+    "el/stall_alerts/subject.txt",
+    "el/stall_alerts/body.html",
+    "el/stall_alerts/body.txt",
+];
+
+pub const DEFAULT_LOCALE: &str = "en";
+pub const SUPPORTED_LOCALES: [&str; 2] = ["en", "el"];
+
+static MISSING: Value = Value::Null;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmailTemplate {
+    VerifyEmail,
+    PasswordReset,
+    StallAlerts,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl EmailTemplate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmailTemplate::VerifyEmail => "verify_email",
+            EmailTemplate::PasswordReset => "password_reset",
+            EmailTemplate::StallAlerts => "stall_alerts",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<EmailTemplate> {
+        [
+            EmailTemplate::VerifyEmail,
+            EmailTemplate::PasswordReset,
+            EmailTemplate::StallAlerts,
+        ]
+        .into_iter()
+        .find(|template| template.name() == name)
+    }
+
+    // Made up values for previewing a template without sending it
+    pub fn sample_context(&self) -> Value {
+        match self {
+            EmailTemplate::VerifyEmail => json!({
+                "name": "Alex",
+                "verify_url": "https://verifit.xyz/users/verify-email?username=alex@example.com&token=preview",
+            }),
+            EmailTemplate::PasswordReset => json!({
+                "name": "Alex",
+                "reset_code": "123456",
+            }),
+            EmailTemplate::StallAlerts => json!({
+                "name": "Alex",
+                "unit": "kg",
+                "alerts": [
+                    {
+                        "exercise_name": "Flat Barbell Bench Press",
+                        "best_one_rep_max": "102.5",
+                        "best_at": "2026-08-30",
+                        "weeks_stalled": 5,
+                    },
+                    {
+                        "exercise_name": "Barbell Squat",
+                        "best_one_rep_max": "140.0",
+                        "best_at": "2026-08-16",
+                        "weeks_stalled": 7,
+                    },
+                ],
+            }),
+        }
+    }
+}
+
+pub fn is_supported_locale(locale: &str) -> bool {
+    SUPPORTED_LOCALES.contains(&locale)
+}
+
+// None when neither the override directory nor the binary has the file
+fn read_file(path: &str) -> Result<Option<String>, String> {
+    if let Ok(templates_dir) = std::env::var("EMAIL_TEMPLATES_DIR") {
+        let override_path = Path::new(&templates_dir).join(path);
+        match fs::read_to_string(&override_path) {
+            Ok(template) => return Ok(Some(template)),
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("{}: {}", override_path.display(), err)),
+        }
+    }
+
+    Ok(EMBEDDED_TEMPLATES
+        .iter()
+        .find(|(embedded_path, _)| *embedded_path == path)
+        .map(|(_, template)| template.to_string()))
+}
+
+fn require_file(path: &str) -> Result<String, String> {
+    read_file(path)?.ok_or_else(|| format!("missing email template {}", path))
+}
+
+// Files missing from a translation fall back to the default locale
+fn read_localized(locale: &str, file: &str) -> Result<String, String> {
+    match read_file(&format!("{}/{}", locale, file))? {
+        Some(template) => Ok(template),
+        None => require_file(&format!("{}/{}", DEFAULT_LOCALE, file)),
+    }
+}
+
+pub fn render_email(
+    template: EmailTemplate,
+    locale: Option<&str>,
+    context: &Value,
+) -> Result<RenderedEmail, String> {
+    let locale = locale
+        .filter(|locale| is_supported_locale(locale))
+        .unwrap_or(DEFAULT_LOCALE);
+    let file = |name: &str| read_localized(locale, &format!("{}/{}", template.name(), name));
+
+    let subject = render(&file("subject.txt")?, context, false)?
+        .trim()
+        .to_string();
+    let html = render(&file("body.html")?, context, true)?;
+    let text = render(&file("body.txt")?, context, false)?;
+
+    let footer = read_localized(locale, "footer.txt")?;
+    let layout_context = |content: String| {
+        json!({
+            "subject": subject,
+            "content": content.trim_end(),
+            "footer": footer.trim(),
+        })
+    };
+    let html = render(&require_file("layout.html")?, &layout_context(html), true)?;
+    let text = render(&require_file("layout.txt")?, &layout_context(text), false)?;
+
+    Ok(RenderedEmail {
+        subject,
+        html,
+        text,
+    })
+}
+
+// The part of mustache the emails need. {{name}} is escaped in HTML and {{{name}}} never is,
+// {{#name}}..{{/name}} repeats for every item of a list or renders once for any other truthy
+// value and {{^name}}..{{/name}} renders when the value is missing or empty. Names not found in
+// a section's item are looked up in the enclosing context.
+pub fn render(template: &str, context: &Value, escape: bool) -> Result<String, String> {
+    render_scoped(template, &[context], escape)
+}
+
+fn render_scoped(template: &str, scopes: &[&Value], escape: bool) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let tag = &rest[start..];
+
+        if let Some(tag) = tag.strip_prefix("{{{") {
+            let end = tag.find("}}}").ok_or("unclosed {{{ tag")?;
+            output.push_str(&to_text(lookup(scopes, tag[..end].trim())));
+            rest = &tag[end + 3..];
+            continue;
+        }
+
+        let tag = &tag[2..];
+        let end = tag.find("}}").ok_or("unclosed {{ tag")?;
+        let name = tag[..end].trim();
+        rest = &tag[end + 2..];
+
+        if let Some(section) = name.strip_prefix('#').or_else(|| name.strip_prefix('^')) {
+            let section = section.trim();
+            let closing = format!("{{{{/{}}}}}", section);
+            let close = rest
+                .find(&closing)
+                .ok_or_else(|| format!("unclosed section {}", section))?;
+            let inner = &rest[..close];
+            rest = &rest[close + closing.len()..];
+
+            let value = lookup(scopes, section);
+            if name.starts_with('^') {
+                if !is_truthy(value) {
+                    output.push_str(&render_scoped(inner, scopes, escape)?);
+                }
+                continue;
+            }
+
+            let items = match value {
+                Value::Array(items) => items.iter().collect(),
+                value if is_truthy(value) => vec![value],
+                _ => vec![],
+            };
+            for item in items {
+                let mut item_scopes = scopes.to_vec();
+                item_scopes.push(item);
+                output.push_str(&render_scoped(inner, &item_scopes, escape)?);
+            }
+        } else if name.starts_with('/') {
+            return Err(format!("unexpected closing tag {}", name));
+        } else {
+            let text = to_text(lookup(scopes, name));
+            if escape {
+                output.push_str(&escape_html(&text));
+            } else {
+                output.push_str(&text);
+            }
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn lookup<'a>(scopes: &[&'a Value], name: &str) -> &'a Value {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.get(name))
+        .unwrap_or(&MISSING)
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::String(value) => !value.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => true,
+    }
+}
+
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_template_renders_in_every_locale() {
+        for name in ["verify_email", "password_reset", "stall_alerts"] {
+            let template = EmailTemplate::from_name(name).unwrap();
+            for locale in SUPPORTED_LOCALES {
+                let email = render_email(template, Some(locale), &template.sample_context())
+                    .unwrap_or_else(|err| panic!("{} {}: {}", name, locale, err));
+                assert!(!email.subject.is_empty());
+                assert!(email.html.contains("Alex"));
+                assert!(email.text.contains("Alex"));
+            }
+        }
+    }
+
+    #[test]
+    fn html_values_are_escaped_unless_triple_braced() {
+        let context = json!({ "name": "<b>Alex</b>" });
+        assert_eq!(
+            render("{{name}} {{{name}}}", &context, true).unwrap(),
+            "&lt;b&gt;Alex&lt;/b&gt; <b>Alex</b>"
+        );
+    }
+
+    #[test]
+    fn sections_repeat_and_inverted_sections_render_when_empty() {
+        let context = json!({ "unit": "kg", "alerts": [{ "weight": 100 }, { "weight": 110 }] });
+        assert_eq!(
+            render("{{#alerts}}{{weight}}{{unit}} {{/alerts}}", &context, false).unwrap(),
+            "100kg 110kg "
+        );
+        assert_eq!(
+            render("{{^missing}}none{{/missing}}", &context, false).unwrap(),
+            "none"
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use crate::database::sea_orm_active_enums::{Equipment, Muscle, TrackingType};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+// Curated exercises every user can pick from, keyed by a stable slug so renames in the file
+// update the existing rows instead of adding new ones
+const BUILTIN_LIBRARY: &str = include_str!("../../data/exercises.json");
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LibraryEntry {
+    pub key: String,
+    pub name: String,
+    pub category: String,
+    pub equipment: Equipment,
+    #[serde(default = "default_tracking_type")]
+    pub tracking_type: TrackingType,
+    #[serde(default)]
+    pub primary_muscles: Vec<Muscle>,
+    #[serde(default)]
+    pub secondary_muscles: Vec<Muscle>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+fn default_tracking_type() -> TrackingType {
+    TrackingType::WeightReps
+}
+
+// Parsed once, `run` touches it before serving so a bad file stops the server at boot
+static PARSED_BUILTIN_LIBRARY: LazyLock<Vec<LibraryEntry>> = LazyLock::new(|| {
+    serde_json::from_str(BUILTIN_LIBRARY)
+        .unwrap_or_else(|err| panic!("built-in exercise library is invalid: {}", err))
+});
+
+pub fn builtin_library() -> &'static [LibraryEntry] {
+    &PARSED_BUILTIN_LIBRARY
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate};
+
+// Trends that only reach the target further out than this aren't worth projecting
+const MAX_PROJECTION_DAYS: f64 = 5.0 * 365.0;
+
+#[derive(Debug, Clone)]
+pub struct GoalPoint {
+    pub date: DateTime<FixedOffset>,
+    pub value: f64,
+}
+
+// Goals can point either way, a bodyweight goal is usually below where the user started
+pub fn is_reached(start: f64, current: f64, target: f64) -> bool {
+    if target >= start {
+        current >= target
+    } else {
+        current <= target
+    }
+}
+
+pub fn progress_percent(start: f64, current: f64, target: f64) -> f64 {
+    if is_reached(start, current, target) {
+        return 100.0;
+    }
+    ((current - start) / (target - start) * 100.0).clamp(0.0, 100.0)
+}
+
+// Least squares line through the points, extended until it crosses the target. Points must be
+// sorted oldest first and span at least two days, a trend moving away from the target has no
+// projection.
+pub fn project_completion(points: &[GoalPoint], target: f64) -> Option<NaiveDate> {
+    let first = points.first()?.date;
+    let days: Vec<f64> = points
+        .iter()
+        .map(|point| (point.date - first).num_seconds() as f64 / 86400.0)
+        .collect();
+    let count = points.len() as f64;
+    let mean_day = days.iter().sum::<f64>() / count;
+    let mean_value = points.iter().map(|point| point.value).sum::<f64>() / count;
+
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (day, point) in days.iter().zip(points) {
+        covariance += (day - mean_day) * (point.value - mean_value);
+        variance += (day - mean_day) * (day - mean_day);
+    }
+    if variance < 1.0 || covariance == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance;
+    let intercept = mean_value - slope * mean_day;
+    let last_day = days.last().copied()?;
+    let target_day = (target - intercept) / slope;
+    if target_day < last_day || target_day - last_day > MAX_PROJECTION_DAYS {
+        return None;
+    }
+
+    Some((first + Duration::seconds((target_day * 86400.0) as i64)).date_naive())
+}
@@ -1,3 +1,24 @@
+pub mod achievements;
+pub mod email_templates;
+pub mod exercise_library;
+pub mod goals;
 pub mod jwt;
+pub mod muscles;
+pub mod nullable;
+pub mod plates;
+pub mod programs;
+pub mod progression;
+pub mod recurrence;
+pub mod scores;
+pub mod search;
+pub mod set_filter;
+pub mod set_timing;
+pub mod set_validation;
+pub mod stalls;
+pub mod streaks;
+pub mod strength;
+pub mod time;
+pub mod track_import;
+pub mod units;
 
 use jwt::{create_jwt, is_valid};
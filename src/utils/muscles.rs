@@ -0,0 +1,72 @@
+use crate::database::sea_orm_active_enums::{Muscle, MuscleRole};
+use sea_orm::Iterable;
+
+// A set counts fully toward the muscles it targets and half toward the ones that assist
+pub fn set_share(role: MuscleRole) -> f64 {
+    match role {
+        MuscleRole::Primary => 1.0,
+        MuscleRole::Secondary => 0.5,
+    }
+}
+
+// Used for exercises without an explicit mapping, every muscle of the category counts as
+// primary. User-defined categories and cardio don't map to any muscle.
+pub fn fallback_muscles(category: &str) -> &'static [Muscle] {
+    match category.to_ascii_lowercase().as_str() {
+        "abs" => &[Muscle::Abs, Muscle::Obliques],
+        "back" => &[Muscle::Lats, Muscle::UpperBack],
+        "biceps" => &[Muscle::Biceps],
+        "chest" => &[Muscle::Chest],
+        "legs" => &[Muscle::Quads, Muscle::Hamstrings, Muscle::Glutes],
+        "shoulders" => &[Muscle::FrontDelts, Muscle::SideDelts, Muscle::RearDelts],
+        "triceps" => &[Muscle::Triceps],
+        "glutes" => &[Muscle::Glutes],
+        "calves" => &[Muscle::Calves],
+        "forearms" => &[Muscle::Forearms],
+        "full body" => &[
+            Muscle::Chest,
+            Muscle::Lats,
+            Muscle::UpperBack,
+            Muscle::FrontDelts,
+            Muscle::Quads,
+            Muscle::Hamstrings,
+            Muscle::Glutes,
+        ],
+        _ => &[],
+    }
+}
+
+// A set of an unmapped exercise counts as one set split evenly over its fallback muscles, so a
+// legs set doesn't add a full set to each of quads, hamstrings and glutes
+pub fn fallback_shares(category: &str) -> Vec<(Muscle, f64)> {
+    let muscles = fallback_muscles(category);
+    muscles
+        .iter()
+        .map(|muscle| (*muscle, 1.0 / muscles.len() as f64))
+        .collect()
+}
+
+// Weighted overlap of two mappings, 1.0 when they work the same muscles in the same roles
+pub fn muscle_similarity(left: &[(Muscle, MuscleRole)], right: &[(Muscle, MuscleRole)]) -> f64 {
+    let share = |muscles: &[(Muscle, MuscleRole)], muscle: Muscle| {
+        muscles
+            .iter()
+            .filter(|(mapped, _)| *mapped == muscle)
+            .map(|(_, role)| set_share(*role))
+            .fold(0.0, f64::max)
+    };
+
+    let mut overlap = 0.0;
+    let mut total = 0.0;
+    for muscle in Muscle::iter() {
+        let left_share = share(left, muscle);
+        let right_share = share(right, muscle);
+        overlap += left_share.min(right_share);
+        total += left_share.max(right_share);
+    }
+
+    if total == 0.0 {
+        return 0.0;
+    }
+    overlap / total
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Deserializer};
+
+// For clearable fields of a partial update, used with `#[serde(default)]`. A missing key stays
+// None and keeps the stored value, an explicit null becomes Some(None) and clears it.
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// The value a clearable field ends up with once the request is applied on top of the stored one
+pub fn merge<T: Clone>(requested: &Option<Option<T>>, stored: &Option<T>) -> Option<T> {
+    match requested {
+        Some(value) => value.clone(),
+        None => stored.clone(),
+    }
+}
@@ -0,0 +1,263 @@
+use crate::database::sea_orm_active_enums::WeightUnit;
+use serde::{Deserialize, Serialize};
+
+// Plates are matched in thousandths so 1.25 kg and 2.5 lb plates add up exactly
+const PRECISION: f64 = 1000.0;
+// Smallest plate increment accepted, keeps the search in load_bar small
+const PLATE_STEP: usize = 50;
+
+// Percent of the working weight and reps, the empty bar set comes before these
+const WARM_UP_RAMP: [(f64, i32); 3] = [(40.0, 5), (60.0, 3), (80.0, 2)];
+const EMPTY_BAR_REPS: i32 = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Plate {
+    pub weight: f64,
+    // Total plates owned, half of them can go on each side
+    pub count: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct BarLoad {
+    pub weight: f64,
+    pub per_side: Vec<Plate>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WarmUpSet {
+    pub weight: f64,
+    pub reps: i32,
+    pub per_side: Vec<Plate>,
+}
+
+pub fn default_bar_weight(unit: WeightUnit) -> f64 {
+    match unit {
+        WeightUnit::Kg => 20.0,
+        WeightUnit::Lb => 45.0,
+    }
+}
+
+// What a typical commercial gym has, used until the user stores their own inventory
+pub fn default_plates(unit: WeightUnit) -> Vec<Plate> {
+    let weights: &[f64] = match unit {
+        WeightUnit::Kg => &[25.0, 20.0, 15.0, 10.0, 5.0, 2.5, 1.25],
+        WeightUnit::Lb => &[45.0, 35.0, 25.0, 10.0, 5.0, 2.5],
+    };
+    weights
+        .iter()
+        .map(|weight| Plate {
+            weight: *weight,
+            count: 8,
+        })
+        .collect()
+}
+
+pub fn validate_inventory(bar_weight: f64, plates: &[Plate]) -> Result<(), String> {
+    if !(0.0..=100.0).contains(&bar_weight) {
+        return Err(format!("invalid bar weight {}", bar_weight));
+    }
+    if plates.len() > 20 {
+        return Err(String::from("no more than 20 plate sizes"));
+    }
+    for plate in plates {
+        let is_valid_weight = plate.weight > 0.0
+            && plate.weight <= 100.0
+            && to_units(plate.weight).is_multiple_of(PLATE_STEP);
+        if !is_valid_weight || plate.count > 100 {
+            return Err(format!("invalid plate {:?}", plate));
+        }
+    }
+    Ok(())
+}
+
+fn to_units(weight: f64) -> usize {
+    (weight * PRECISION).round().max(0.0) as usize
+}
+
+fn greatest_common_divisor(left: usize, right: usize) -> usize {
+    if right == 0 {
+        left
+    } else {
+        greatest_common_divisor(right, left % right)
+    }
+}
+
+// The heaviest load not above `target` that the plates can make, using as few plates as
+// possible. Each side gets the same plates, heaviest first.
+pub fn load_bar(target: f64, bar_weight: f64, plates: &[Plate]) -> BarLoad {
+    let mut plates: Vec<Plate> = plates
+        .iter()
+        .filter(|plate| plate.count >= 2)
+        .copied()
+        .collect();
+    plates.sort_by(|left, right| right.weight.total_cmp(&left.weight));
+
+    let per_side_target = to_units(((target - bar_weight) / 2.0).max(0.0));
+    let step = plates
+        .iter()
+        .map(|plate| to_units(plate.weight))
+        .fold(0, greatest_common_divisor);
+    if step == 0 || per_side_target < step {
+        return BarLoad {
+            weight: bar_weight,
+            per_side: Vec::new(),
+        };
+    }
+
+    // Bounded knapsack over multiples of `step`, one plate size at a time. plates_used[sum] is
+    // the fewest plates that make `sum` and plate_counts[sum] how many of each size that takes.
+    // Sums are visited downwards so every size is only added once per sum.
+    let slots = per_side_target / step;
+    let mut plates_used: Vec<Option<u32>> = vec![None; slots + 1];
+    let mut plate_counts: Vec<Vec<u32>> = vec![Vec::new(); slots + 1];
+    plates_used[0] = Some(0);
+    plate_counts[0] = vec![0; plates.len()];
+
+    for (index, plate) in plates.iter().enumerate() {
+        let size = to_units(plate.weight) / step;
+        let available = plate.count / 2;
+        for sum in (0..=slots).rev() {
+            for used in 1..=available as usize {
+                if used * size > sum {
+                    break;
+                }
+                let from = sum - used * size;
+                let Some(from_plates) = plates_used[from] else {
+                    continue;
+                };
+                let candidate = from_plates + used as u32;
+                if plates_used[sum].is_none_or(|current| candidate < current) {
+                    plates_used[sum] = Some(candidate);
+                    let mut counts = plate_counts[from].clone();
+                    counts[index] = used as u32;
+                    plate_counts[sum] = counts;
+                }
+            }
+        }
+    }
+
+    let best = (0..=slots)
+        .rev()
+        .find(|sum| plates_used[*sum].is_some())
+        .unwrap_or(0);
+    let per_side: Vec<Plate> = plates
+        .iter()
+        .zip(&plate_counts[best])
+        .filter(|(_, count)| **count > 0)
+        .map(|(plate, count)| Plate {
+            weight: plate.weight,
+            count: *count,
+        })
+        .collect();
+
+    BarLoad {
+        weight: bar_weight + 2.0 * (best * step) as f64 / PRECISION,
+        per_side,
+    }
+}
+
+// Empty bar, then 40, 60 and 80 percent of the working weight, each rounded down to what the
+// plates can make. Steps that land on the bar or on a previous step are dropped.
+pub fn warm_up_ramp(target: f64, bar_weight: f64, plates: &[Plate]) -> Vec<WarmUpSet> {
+    if target <= bar_weight {
+        return Vec::new();
+    }
+
+    let mut ramp = vec![WarmUpSet {
+        weight: bar_weight,
+        reps: EMPTY_BAR_REPS,
+        per_side: Vec::new(),
+    }];
+    for (percent, reps) in WARM_UP_RAMP {
+        let load = load_bar(target * percent / 100.0, bar_weight, plates);
+        let is_new = ramp
+            .last()
+            .is_some_and(|previous| load.weight > previous.weight);
+        if is_new && load.weight < target {
+            ramp.push(WarmUpSet {
+                weight: load.weight,
+                reps,
+                per_side: load.per_side,
+            });
+        }
+    }
+
+    ramp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plates(plates: &[(f64, u32)]) -> Vec<Plate> {
+        plates
+            .iter()
+            .map(|(weight, count)| Plate {
+                weight: *weight,
+                count: *count,
+            })
+            .collect()
+    }
+
+    fn plate_count(load: &BarLoad) -> u32 {
+        load.per_side.iter().map(|plate| plate.count).sum()
+    }
+
+    #[test]
+    fn loads_round_down_to_what_the_plates_make() {
+        let kg_plates = default_plates(WeightUnit::Kg);
+        assert_eq!(load_bar(100.0, 20.0, &kg_plates).weight, 100.0);
+        assert_eq!(load_bar(101.0, 20.0, &kg_plates).weight, 100.0);
+        assert_eq!(load_bar(102.6, 20.0, &kg_plates).weight, 102.5);
+        assert_eq!(load_bar(15.0, 20.0, &kg_plates).weight, 20.0);
+    }
+
+    #[test]
+    fn loads_use_as_few_plates_as_possible() {
+        let load = load_bar(140.0, 20.0, &default_plates(WeightUnit::Kg));
+        assert_eq!(load.weight, 140.0);
+        assert_eq!(plate_count(&load), 3);
+    }
+
+    #[test]
+    fn plate_counts_bound_the_load() {
+        // Greedy would take the 25 and get stuck, two 15s per side make the target
+        let load = load_bar(80.0, 20.0, &plates(&[(25.0, 2), (15.0, 4)]));
+        assert_eq!(load.weight, 80.0);
+        assert_eq!(load.per_side, plates(&[(15.0, 2)]));
+
+        // One 10 and two 2.5s per side at most
+        let limited = plates(&[(10.0, 2), (2.5, 4), (1.25, 1)]);
+        assert_eq!(load_bar(50.0, 20.0, &limited).weight, 50.0);
+        assert_eq!(load_bar(60.0, 20.0, &limited).weight, 50.0);
+    }
+
+    #[test]
+    fn fractional_plates_add_up_exactly() {
+        let load = load_bar(22.5, 20.0, &plates(&[(1.25, 2)]));
+        assert_eq!(load.weight, 22.5);
+        assert_eq!(load.per_side, plates(&[(1.25, 1)]));
+    }
+
+    #[test]
+    fn warm_up_ramps_towards_the_working_weight() {
+        let ramp = warm_up_ramp(100.0, 20.0, &default_plates(WeightUnit::Kg));
+        let steps: Vec<(f64, i32)> = ramp.iter().map(|set| (set.weight, set.reps)).collect();
+        assert_eq!(steps, vec![(20.0, 10), (40.0, 5), (60.0, 3), (80.0, 2)]);
+    }
+
+    #[test]
+    fn light_working_weights_only_warm_up_with_the_bar() {
+        let kg_plates = default_plates(WeightUnit::Kg);
+        assert_eq!(warm_up_ramp(25.0, 20.0, &kg_plates).len(), 1);
+        assert!(warm_up_ramp(20.0, 20.0, &kg_plates).is_empty());
+    }
+
+    #[test]
+    fn inventory_rejects_odd_plates() {
+        assert!(validate_inventory(20.0, &default_plates(WeightUnit::Lb)).is_ok());
+        assert!(validate_inventory(150.0, &[]).is_err());
+        assert!(validate_inventory(20.0, &plates(&[(1.33, 2)])).is_err());
+        assert!(validate_inventory(20.0, &plates(&[(5.0, 101)])).is_err());
+    }
+}
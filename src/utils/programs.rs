@@ -0,0 +1,594 @@
+use crate::database::sea_orm_active_enums::WeightUnit;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+// Built-in programs use the same declarative format users author, so they double as examples
+const BUILTIN_PROGRAMS: [(&str, &str); 3] = [
+    (
+        "linear-progression",
+        include_str!("../../data/programs/linear-progression.json"),
+    ),
+    ("531", include_str!("../../data/programs/531.json")),
+    ("gzclp", include_str!("../../data/programs/gzclp.json")),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressionTrigger {
+    // Training max moves after every successful session (linear progression, GZCLP)
+    #[default]
+    Session,
+    // Training max moves once at the end of each cycle through all weeks (5/3/1)
+    Cycle,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetScheme {
+    pub reps: i32,
+    // Percentage of the lift's training max
+    #[serde(default = "default_percent")]
+    pub percent: f64,
+    #[serde(default)]
+    pub amrap: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiftDefinition {
+    pub exercise_name: String,
+    pub category: String,
+    pub increment: f64,
+    #[serde(default)]
+    pub progression: ProgressionTrigger,
+    #[serde(default = "default_deload_after_failures")]
+    pub deload_after_failures: u32,
+    #[serde(default = "default_deload_percent")]
+    pub deload_percent: f64,
+    // Share of the estimated 1RM used when enrolling without an explicit training max
+    #[serde(default = "default_training_max_percent")]
+    pub training_max_percent: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlotDefinition {
+    pub lift: String,
+    #[serde(default)]
+    pub sets: Vec<SetScheme>,
+    // Alternative set schemes a lift falls back to on failure, e.g. GZCLP's 5x3, 6x2, 10x1
+    #[serde(default)]
+    pub stages: Vec<Vec<SetScheme>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DayDefinition {
+    pub name: String,
+    pub slots: Vec<SlotDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WeekDefinition {
+    pub days: Vec<DayDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProgramDefinition {
+    pub name: String,
+    pub description: Option<String>,
+    // Unit of increments, rounding and training maxes
+    #[serde(default = "default_unit")]
+    pub unit: WeightUnit,
+    #[serde(default = "default_rounding")]
+    pub rounding: f64,
+    pub lifts: BTreeMap<String, LiftDefinition>,
+    pub weeks: Vec<WeekDefinition>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LiftState {
+    pub training_max: f64,
+    #[serde(default)]
+    pub stage: usize,
+    #[serde(default)]
+    pub failures: u32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlannedProgramSet {
+    pub reps: i32,
+    pub weight: f64,
+    pub percent: f64,
+    pub amrap: bool,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PlannedSlot {
+    pub lift: String,
+    pub exercise_name: String,
+    pub category: String,
+    pub training_max: f64,
+    pub stage: usize,
+    pub sets: Vec<PlannedProgramSet>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedSet {
+    pub exercise_name: String,
+    pub reps: i32,
+    // Already converted to the program's unit
+    pub weight: f64,
+    pub date: DateTime<FixedOffset>,
+}
+
+pub struct DayOutcome {
+    // Date of the last logged set consumed by the day, the next day only looks at later sets
+    pub finished_at: Option<DateTime<FixedOffset>>,
+    pub consumed: Vec<usize>,
+    pub lift_results: BTreeMap<String, bool>,
+}
+
+fn default_percent() -> f64 {
+    100.0
+}
+
+fn default_deload_after_failures() -> u32 {
+    3
+}
+
+fn default_deload_percent() -> f64 {
+    90.0
+}
+
+fn default_training_max_percent() -> f64 {
+    90.0
+}
+
+fn default_unit() -> WeightUnit {
+    WeightUnit::Kg
+}
+
+fn default_rounding() -> f64 {
+    2.5
+}
+
+// Parsed once, `run` touches them before serving so a bad file stops the server at boot
+static PARSED_BUILTIN_PROGRAMS: LazyLock<Vec<(String, ProgramDefinition)>> = LazyLock::new(|| {
+    BUILTIN_PROGRAMS
+        .iter()
+        .map(|(key, definition)| {
+            let definition: ProgramDefinition = serde_json::from_str(definition)
+                .unwrap_or_else(|err| panic!("built-in program {} is invalid: {}", key, err));
+            definition
+                .validate()
+                .unwrap_or_else(|err| panic!("built-in program {} is invalid: {}", key, err));
+            (key.to_string(), definition)
+        })
+        .collect()
+});
+
+pub fn builtin_programs() -> &'static [(String, ProgramDefinition)] {
+    &PARSED_BUILTIN_PROGRAMS
+}
+
+impl SlotDefinition {
+    fn stage_count(&self) -> usize {
+        if self.stages.is_empty() {
+            1
+        } else {
+            self.stages.len()
+        }
+    }
+
+    fn stage_sets(&self, stage: usize) -> &[SetScheme] {
+        if self.stages.is_empty() {
+            &self.sets
+        } else {
+            &self.stages[stage.min(self.stages.len() - 1)]
+        }
+    }
+}
+
+impl ProgramDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err(String::from("program needs a name"));
+        }
+        if self.rounding <= 0.0 {
+            return Err(String::from("rounding must be positive"));
+        }
+        if self.weeks.is_empty() || self.weeks.iter().any(|week| week.days.is_empty()) {
+            return Err(String::from("every week needs at least one day"));
+        }
+
+        for (key, lift) in &self.lifts {
+            if lift.increment < 0.0
+                || !(0.0..=100.0).contains(&lift.deload_percent)
+                || !(0.0..=100.0).contains(&lift.training_max_percent)
+            {
+                return Err(format!("lift {} has an invalid increment or deload", key));
+            }
+        }
+
+        for day in self.weeks.iter().flat_map(|week| &week.days) {
+            if day.slots.is_empty() {
+                return Err(format!("day {} has no lifts", day.name));
+            }
+            for slot in &day.slots {
+                if !self.lifts.contains_key(&slot.lift) {
+                    return Err(format!("day {} uses unknown lift {}", day.name, slot.lift));
+                }
+                if !slot.stages.is_empty() && !slot.sets.is_empty() {
+                    return Err(format!("lift {} has both sets and stages", slot.lift));
+                }
+                let schemes = (0..slot.stage_count()).map(|stage| slot.stage_sets(stage));
+                for sets in schemes {
+                    if sets.is_empty()
+                        || sets
+                            .iter()
+                            .any(|set| set.reps < 1 || !(0.0..=200.0).contains(&set.percent))
+                    {
+                        return Err(format!("lift {} has an invalid set scheme", slot.lift));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn day(&self, week: usize, day: usize) -> Option<&DayDefinition> {
+        self.weeks.get(week).and_then(|week| week.days.get(day))
+    }
+
+    pub fn round_weight(&self, weight: f64) -> f64 {
+        (weight / self.rounding).round() * self.rounding
+    }
+
+    pub fn plan_day(
+        &self,
+        state: &BTreeMap<String, LiftState>,
+        week: usize,
+        day: usize,
+    ) -> Vec<PlannedSlot> {
+        let Some(day) = self.day(week, day) else {
+            return Vec::new();
+        };
+
+        day.slots
+            .iter()
+            .filter_map(|slot| {
+                let lift = self.lifts.get(&slot.lift)?;
+                let lift_state = state.get(&slot.lift)?;
+
+                Some(PlannedSlot {
+                    lift: slot.lift.clone(),
+                    exercise_name: lift.exercise_name.clone(),
+                    category: lift.category.clone(),
+                    training_max: lift_state.training_max,
+                    stage: lift_state.stage,
+                    sets: slot
+                        .stage_sets(lift_state.stage)
+                        .iter()
+                        .map(|set| PlannedProgramSet {
+                            reps: set.reps,
+                            weight: self
+                                .round_weight(lift_state.training_max * set.percent / 100.0),
+                            percent: set.percent,
+                            amrap: set.amrap,
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    // Matches logged sets (oldest first) against the day's plan. Returns None while any slot
+    // is still missing sets, unless `force` is set, in which case missing sets count as misses.
+    pub fn evaluate_day(
+        &self,
+        state: &BTreeMap<String, LiftState>,
+        week: usize,
+        day: usize,
+        logged_sets: &[LoggedSet],
+        force: bool,
+    ) -> Option<DayOutcome> {
+        let planned_slots = self.plan_day(state, week, day);
+        let mut used = vec![false; logged_sets.len()];
+        let mut finished_at = None;
+        let mut lift_results: BTreeMap<String, bool> = BTreeMap::new();
+
+        for slot in &planned_slots {
+            let matching: Vec<usize> = logged_sets
+                .iter()
+                .enumerate()
+                .filter(|(index, logged_set)| {
+                    !used[*index]
+                        && logged_set
+                            .exercise_name
+                            .trim()
+                            .eq_ignore_ascii_case(slot.exercise_name.trim())
+                })
+                .map(|(index, _)| index)
+                .take(slot.sets.len())
+                .collect();
+
+            if matching.len() < slot.sets.len() && !force {
+                return None;
+            }
+
+            let mut succeeded = matching.len() == slot.sets.len();
+            for (planned_set, index) in slot.sets.iter().zip(&matching) {
+                let logged_set = &logged_sets[*index];
+                used[*index] = true;
+                finished_at = finished_at.max(Some(logged_set.date));
+
+                // Half a rounding step of slack for plates that don't load exactly
+                let heavy_enough = logged_set.weight + self.rounding / 2.0 >= planned_set.weight;
+                if logged_set.reps < planned_set.reps || !heavy_enough {
+                    succeeded = false;
+                }
+            }
+
+            let result = lift_results.entry(slot.lift.clone()).or_insert(true);
+            *result = *result && succeeded;
+        }
+
+        Some(DayOutcome {
+            finished_at,
+            consumed: (0..logged_sets.len())
+                .filter(|index| used[*index])
+                .collect(),
+            lift_results,
+        })
+    }
+
+    fn stage_count(&self, lift: &str) -> usize {
+        self.weeks
+            .iter()
+            .flat_map(|week| &week.days)
+            .flat_map(|day| &day.slots)
+            .filter(|slot| slot.lift == lift)
+            .map(SlotDefinition::stage_count)
+            .max()
+            .unwrap_or(1)
+    }
+
+    pub fn apply_day_outcome(&self, state: &mut BTreeMap<String, LiftState>, outcome: &DayOutcome) {
+        for (key, succeeded) in &outcome.lift_results {
+            let (Some(lift), Some(lift_state)) = (self.lifts.get(key), state.get_mut(key)) else {
+                continue;
+            };
+
+            if lift.progression == ProgressionTrigger::Cycle {
+                // Judged once the cycle ends
+                if !succeeded {
+                    lift_state.failures += 1;
+                }
+                continue;
+            }
+
+            if *succeeded {
+                lift_state.training_max += lift.increment;
+                lift_state.failures = 0;
+                continue;
+            }
+
+            if lift_state.stage + 1 < self.stage_count(key) {
+                lift_state.stage += 1;
+                lift_state.failures = 0;
+                continue;
+            }
+
+            lift_state.failures += 1;
+            if lift_state.failures >= lift.deload_after_failures {
+                lift_state.training_max =
+                    self.round_weight(lift_state.training_max * lift.deload_percent / 100.0);
+                lift_state.stage = 0;
+                lift_state.failures = 0;
+            }
+        }
+    }
+
+    pub fn apply_cycle_end(&self, state: &mut BTreeMap<String, LiftState>) {
+        for (key, lift_state) in state.iter_mut() {
+            let Some(lift) = self.lifts.get(key) else {
+                continue;
+            };
+            if lift.progression != ProgressionTrigger::Cycle {
+                continue;
+            }
+
+            if lift_state.failures >= lift.deload_after_failures {
+                lift_state.training_max =
+                    self.round_weight(lift_state.training_max * lift.deload_percent / 100.0);
+            } else {
+                lift_state.training_max += lift.increment;
+            }
+            lift_state.failures = 0;
+        }
+    }
+
+    // Returns the next (week, day) and whether a cycle was just completed
+    pub fn next_day(&self, week: usize, day: usize) -> (usize, usize, bool) {
+        let days_in_week = self.weeks.get(week).map_or(0, |week| week.days.len());
+
+        if day + 1 < days_in_week {
+            (week, day + 1, false)
+        } else if week + 1 < self.weeks.len() {
+            (week + 1, 0, false)
+        } else {
+            (0, 0, true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Squat progresses every session through two stages, bench once per cycle
+    fn program() -> ProgramDefinition {
+        serde_json::from_value(json!({
+            "name": "Test",
+            "lifts": {
+                "squat": {
+                    "exercise_name": "Squat",
+                    "category": "Legs",
+                    "increment": 5.0,
+                    "deload_after_failures": 2
+                },
+                "bench": {
+                    "exercise_name": "Bench Press",
+                    "category": "Chest",
+                    "increment": 2.5,
+                    "progression": "cycle",
+                    "deload_after_failures": 1
+                }
+            },
+            "weeks": [
+                {"days": [
+                    {"name": "A", "slots": [{"lift": "squat", "stages": [
+                        [{"reps": 5}, {"reps": 5}],
+                        [{"reps": 3}, {"reps": 3}, {"reps": 3}]
+                    ]}]},
+                    {"name": "B", "slots": [{"lift": "bench", "sets": [{"reps": 5, "percent": 80.0}]}]}
+                ]},
+                {"days": [
+                    {"name": "C", "slots": [{"lift": "bench", "sets": [{"reps": 3, "percent": 90.0}]}]}
+                ]}
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn state() -> BTreeMap<String, LiftState> {
+        ["squat", "bench"]
+            .into_iter()
+            .map(|lift| {
+                let lift_state = LiftState {
+                    training_max: 100.0,
+                    stage: 0,
+                    failures: 0,
+                };
+                (lift.to_string(), lift_state)
+            })
+            .collect()
+    }
+
+    fn logged(exercise_name: &str, reps: i32, weight: f64, minute: u32) -> LoggedSet {
+        LoggedSet {
+            exercise_name: exercise_name.to_string(),
+            reps,
+            weight,
+            date: DateTime::parse_from_rfc3339(&format!("2024-01-01T10:{:02}:00Z", minute))
+                .unwrap(),
+        }
+    }
+
+    fn outcome(lift: &str, succeeded: bool) -> DayOutcome {
+        DayOutcome {
+            finished_at: None,
+            consumed: Vec::new(),
+            lift_results: BTreeMap::from([(lift.to_string(), succeeded)]),
+        }
+    }
+
+    #[test]
+    fn builtin_programs_are_valid() {
+        assert_eq!(builtin_programs().len(), BUILTIN_PROGRAMS.len());
+    }
+
+    #[test]
+    fn days_wait_for_every_planned_set() {
+        let program = program();
+        let logged_sets = [logged("Squat", 5, 100.0, 0)];
+        assert!(program
+            .evaluate_day(&state(), 0, 0, &logged_sets, false)
+            .is_none());
+
+        // Forcing the day counts the missing set as a miss
+        let outcome = program
+            .evaluate_day(&state(), 0, 0, &logged_sets, true)
+            .unwrap();
+        assert_eq!(outcome.lift_results.get("squat"), Some(&false));
+        assert_eq!(outcome.consumed, vec![0]);
+    }
+
+    #[test]
+    fn days_consume_only_their_own_lifts() {
+        let logged_sets = [
+            logged("squat", 5, 100.0, 0),
+            logged("Bench Press", 5, 80.0, 5),
+            logged("Squat", 5, 98.75, 10),
+        ];
+        let outcome = program()
+            .evaluate_day(&state(), 0, 0, &logged_sets, false)
+            .unwrap();
+
+        // Within half a rounding step of the plan still counts
+        assert_eq!(outcome.lift_results.get("squat"), Some(&true));
+        assert_eq!(outcome.consumed, vec![0, 2]);
+        assert_eq!(outcome.finished_at, Some(logged_sets[2].date));
+    }
+
+    #[test]
+    fn missed_reps_fail_the_lift() {
+        let logged_sets = [logged("Squat", 5, 100.0, 0), logged("Squat", 4, 100.0, 5)];
+        let outcome = program()
+            .evaluate_day(&state(), 0, 0, &logged_sets, false)
+            .unwrap();
+        assert_eq!(outcome.lift_results.get("squat"), Some(&false));
+    }
+
+    #[test]
+    fn session_lifts_progress_then_change_stage_then_deload() {
+        let program = program();
+        let mut state = state();
+
+        program.apply_day_outcome(&mut state, &outcome("squat", true));
+        assert_eq!(state["squat"].training_max, 105.0);
+
+        program.apply_day_outcome(&mut state, &outcome("squat", false));
+        assert_eq!(state["squat"].stage, 1);
+        assert_eq!(state["squat"].failures, 0);
+
+        program.apply_day_outcome(&mut state, &outcome("squat", false));
+        assert_eq!(state["squat"].failures, 1);
+        assert_eq!(state["squat"].training_max, 105.0);
+
+        // 90% of 105 rounded to the nearest 2.5
+        program.apply_day_outcome(&mut state, &outcome("squat", false));
+        assert_eq!(state["squat"].training_max, 95.0);
+        assert_eq!(state["squat"].stage, 0);
+        assert_eq!(state["squat"].failures, 0);
+    }
+
+    #[test]
+    fn cycle_lifts_only_count_failures_until_the_cycle_ends() {
+        let program = program();
+        let mut state = state();
+
+        program.apply_day_outcome(&mut state, &outcome("bench", true));
+        program.apply_day_outcome(&mut state, &outcome("bench", false));
+        assert_eq!(state["bench"].training_max, 100.0);
+        assert_eq!(state["bench"].failures, 1);
+
+        program.apply_cycle_end(&mut state);
+        assert_eq!(state["bench"].training_max, 90.0);
+        assert_eq!(state["bench"].failures, 0);
+
+        program.apply_cycle_end(&mut state);
+        assert_eq!(state["bench"].training_max, 92.5);
+        // Session lifts are left alone
+        assert_eq!(state["squat"].training_max, 100.0);
+    }
+
+    #[test]
+    fn next_day_walks_the_weeks_and_wraps_around() {
+        let program = program();
+        assert_eq!(program.next_day(0, 0), (0, 1, false));
+        assert_eq!(program.next_day(0, 1), (1, 0, false));
+        assert_eq!(program.next_day(1, 0), (0, 0, true));
+    }
+}
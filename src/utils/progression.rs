@@ -0,0 +1,547 @@
+use crate::database::sea_orm_active_enums::WeightUnit;
+use crate::utils::units::convert_weight;
+use chrono::NaiveDate;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEFAULT_STRATEGY: &str = "double_progression";
+
+static STRATEGIES: [&dyn ProgressionStrategy; 3] =
+    [&DoubleProgression, &LinearProgression, &RpeProgression];
+
+#[derive(Debug, Clone)]
+pub struct SessionSet {
+    pub reps: i32,
+    // Already converted to the unit suggestions are made in
+    pub weight: f64,
+    pub rpe: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub date: NaiveDate,
+    pub sets: Vec<SessionSet>,
+}
+
+impl Session {
+    fn top_weight(&self) -> f64 {
+        self.sets.iter().map(|set| set.weight).fold(0.0, f64::max)
+    }
+
+    // Only sets at the heaviest weight of the day count as work sets, lighter ones are back-offs
+    fn top_sets(&self) -> Vec<&SessionSet> {
+        let top_weight = self.top_weight();
+        self.sets
+            .iter()
+            .filter(|set| set.weight >= top_weight)
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionReason {
+    NoHistory,
+    Increase,
+    Repeat,
+    Decrease,
+    Deload,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Suggestion {
+    pub weight: Option<f64>,
+    pub reps: i32,
+    pub sets: usize,
+    pub reason: SuggestionReason,
+}
+
+pub struct ProgressionContext<'a> {
+    // Oldest first
+    pub sessions: &'a [Session],
+    pub unit: WeightUnit,
+    pub settings: Option<&'a Value>,
+}
+
+pub trait ProgressionStrategy: Sync {
+    fn name(&self) -> &'static str;
+
+    fn validate_settings(&self, settings: Option<&Value>) -> Result<(), String>;
+
+    fn suggest(&self, context: &ProgressionContext) -> Result<Suggestion, String>;
+}
+
+pub fn find_strategy(name: &str) -> Option<&'static dyn ProgressionStrategy> {
+    STRATEGIES
+        .iter()
+        .find(|strategy| strategy.name() == name)
+        .copied()
+}
+
+pub fn strategy_names() -> Vec<&'static str> {
+    STRATEGIES.iter().map(|strategy| strategy.name()).collect()
+}
+
+fn parse_settings<T: DeserializeOwned + Default>(settings: Option<&Value>) -> Result<T, String> {
+    match settings {
+        None | Some(Value::Null) => Ok(T::default()),
+        Some(settings) => serde_json::from_value(settings.clone()).map_err(|err| err.to_string()),
+    }
+}
+
+// Increments are written in their own unit (kg unless stated) and default to the smallest
+// common plate jump, 2.5 kg or 5 lb
+fn increment_in(
+    increment: Option<f64>,
+    increment_unit: Option<WeightUnit>,
+    unit: WeightUnit,
+) -> f64 {
+    match increment {
+        Some(increment) => {
+            convert_weight(increment, increment_unit.unwrap_or(WeightUnit::Kg), unit)
+        }
+        None => match unit {
+            WeightUnit::Kg => 2.5,
+            WeightUnit::Lb => 5.0,
+        },
+    }
+}
+
+// Every suggested weight goes through here, so it can be loaded with the increment's plates
+fn round_to(weight: f64, step: f64) -> f64 {
+    if step <= 0.0 {
+        return weight;
+    }
+    (weight / step).round() * step
+}
+
+fn check_deload(deload_after: u32, deload_percent: f64) -> Result<(), String> {
+    if deload_after == 0 || !(0.0..=100.0).contains(&deload_percent) {
+        return Err(String::from(
+            "deload_after must be positive and deload_percent 0-100",
+        ));
+    }
+    Ok(())
+}
+
+// Number of most recent sessions in a row at the current top weight that missed the target
+fn count_stalls(sessions: &[Session], missed: impl Fn(&Session) -> bool) -> u32 {
+    let Some(last) = sessions.last() else {
+        return 0;
+    };
+    let top_weight = last.top_weight();
+
+    sessions
+        .iter()
+        .rev()
+        .take_while(|session| session.top_weight() >= top_weight && missed(session))
+        .count() as u32
+}
+
+fn no_history(reps: i32, sets: usize) -> Suggestion {
+    Suggestion {
+        weight: None,
+        reps,
+        sets,
+        reason: SuggestionReason::NoHistory,
+    }
+}
+
+// Work up through a rep range at a fixed weight, add weight once every set hits the top of it
+pub struct DoubleProgression;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DoubleProgressionSettings {
+    min_reps: i32,
+    max_reps: i32,
+    increment: Option<f64>,
+    increment_unit: Option<WeightUnit>,
+    deload_after: u32,
+    deload_percent: f64,
+}
+
+impl Default for DoubleProgressionSettings {
+    fn default() -> Self {
+        DoubleProgressionSettings {
+            min_reps: 8,
+            max_reps: 12,
+            increment: None,
+            increment_unit: None,
+            deload_after: 3,
+            deload_percent: 90.0,
+        }
+    }
+}
+
+impl ProgressionStrategy for DoubleProgression {
+    fn name(&self) -> &'static str {
+        "double_progression"
+    }
+
+    fn validate_settings(&self, settings: Option<&Value>) -> Result<(), String> {
+        let settings: DoubleProgressionSettings = parse_settings(settings)?;
+        if settings.min_reps < 1 || settings.max_reps < settings.min_reps {
+            return Err(String::from(
+                "rep range must be 1 or more and min_reps <= max_reps",
+            ));
+        }
+        check_deload(settings.deload_after, settings.deload_percent)
+    }
+
+    fn suggest(&self, context: &ProgressionContext) -> Result<Suggestion, String> {
+        let settings: DoubleProgressionSettings = parse_settings(context.settings)?;
+        let Some(last) = context.sessions.last() else {
+            return Ok(no_history(settings.min_reps, 3));
+        };
+
+        let top_sets = last.top_sets();
+        let top_weight = last.top_weight();
+        let increment = increment_in(settings.increment, settings.increment_unit, context.unit);
+
+        if top_sets.iter().all(|set| set.reps >= settings.max_reps) {
+            return Ok(Suggestion {
+                weight: Some(round_to(top_weight + increment, increment)),
+                reps: settings.min_reps,
+                sets: top_sets.len(),
+                reason: SuggestionReason::Increase,
+            });
+        }
+
+        // A stall is a session that didn't add a single rep over the one before it
+        let rep_totals: Vec<i32> = context
+            .sessions
+            .iter()
+            .map(|session| session.top_sets().iter().map(|set| set.reps).sum())
+            .collect();
+        let stalls = (1..context.sessions.len())
+            .rev()
+            .take_while(|index| {
+                context.sessions[*index].top_weight() >= top_weight
+                    && context.sessions[*index - 1].top_weight() >= top_weight
+                    && rep_totals[*index] <= rep_totals[*index - 1]
+            })
+            .count() as u32;
+
+        if stalls >= settings.deload_after {
+            return Ok(Suggestion {
+                weight: Some(round_to(
+                    top_weight * settings.deload_percent / 100.0,
+                    increment,
+                )),
+                reps: settings.min_reps,
+                sets: top_sets.len(),
+                reason: SuggestionReason::Deload,
+            });
+        }
+
+        let fewest_reps = top_sets.iter().map(|set| set.reps).min().unwrap_or(0);
+        Ok(Suggestion {
+            weight: Some(round_to(top_weight, increment)),
+            reps: (fewest_reps + 1).clamp(settings.min_reps, settings.max_reps),
+            sets: top_sets.len(),
+            reason: SuggestionReason::Repeat,
+        })
+    }
+}
+
+// Fixed sets and reps, add weight after every successful session
+pub struct LinearProgression;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LinearProgressionSettings {
+    sets: usize,
+    reps: i32,
+    increment: Option<f64>,
+    increment_unit: Option<WeightUnit>,
+    deload_after: u32,
+    deload_percent: f64,
+}
+
+impl Default for LinearProgressionSettings {
+    fn default() -> Self {
+        LinearProgressionSettings {
+            sets: 3,
+            reps: 5,
+            increment: None,
+            increment_unit: None,
+            deload_after: 3,
+            deload_percent: 90.0,
+        }
+    }
+}
+
+impl ProgressionStrategy for LinearProgression {
+    fn name(&self) -> &'static str {
+        "linear"
+    }
+
+    fn validate_settings(&self, settings: Option<&Value>) -> Result<(), String> {
+        let settings: LinearProgressionSettings = parse_settings(settings)?;
+        if settings.sets < 1 || settings.reps < 1 {
+            return Err(String::from("sets and reps must be 1 or more"));
+        }
+        check_deload(settings.deload_after, settings.deload_percent)
+    }
+
+    fn suggest(&self, context: &ProgressionContext) -> Result<Suggestion, String> {
+        let settings: LinearProgressionSettings = parse_settings(context.settings)?;
+        let Some(last) = context.sessions.last() else {
+            return Ok(no_history(settings.reps, settings.sets));
+        };
+
+        let top_weight = last.top_weight();
+        let increment = increment_in(settings.increment, settings.increment_unit, context.unit);
+        let missed = |session: &Session| {
+            let top_sets = session.top_sets();
+            top_sets.len() < settings.sets || top_sets.iter().any(|set| set.reps < settings.reps)
+        };
+
+        let (weight, reason) = if !missed(last) {
+            (
+                round_to(top_weight + increment, increment),
+                SuggestionReason::Increase,
+            )
+        } else if count_stalls(context.sessions, missed) >= settings.deload_after {
+            (
+                round_to(top_weight * settings.deload_percent / 100.0, increment),
+                SuggestionReason::Deload,
+            )
+        } else {
+            (round_to(top_weight, increment), SuggestionReason::Repeat)
+        };
+
+        Ok(Suggestion {
+            weight: Some(weight),
+            reps: settings.reps,
+            sets: settings.sets,
+            reason,
+        })
+    }
+}
+
+// Autoregulated: keep the top set at a target RPE, moving the weight by how far off it was
+pub struct RpeProgression;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RpeProgressionSettings {
+    sets: usize,
+    reps: i32,
+    target_rpe: f64,
+    // Share of the weight moved per RPE point off target
+    percent_per_rpe: f64,
+    increment: Option<f64>,
+    increment_unit: Option<WeightUnit>,
+}
+
+impl Default for RpeProgressionSettings {
+    fn default() -> Self {
+        RpeProgressionSettings {
+            sets: 3,
+            reps: 5,
+            target_rpe: 8.0,
+            percent_per_rpe: 4.0,
+            increment: None,
+            increment_unit: None,
+        }
+    }
+}
+
+impl ProgressionStrategy for RpeProgression {
+    fn name(&self) -> &'static str {
+        "rpe"
+    }
+
+    fn validate_settings(&self, settings: Option<&Value>) -> Result<(), String> {
+        let settings: RpeProgressionSettings = parse_settings(settings)?;
+        if settings.sets < 1 || settings.reps < 1 {
+            return Err(String::from("sets and reps must be 1 or more"));
+        }
+        if !(1.0..=10.0).contains(&settings.target_rpe)
+            || !(0.0..=20.0).contains(&settings.percent_per_rpe)
+        {
+            return Err(String::from(
+                "target_rpe must be 1-10 and percent_per_rpe 0-20",
+            ));
+        }
+        Ok(())
+    }
+
+    fn suggest(&self, context: &ProgressionContext) -> Result<Suggestion, String> {
+        let settings: RpeProgressionSettings = parse_settings(context.settings)?;
+        let Some(last) = context.sessions.last() else {
+            return Ok(no_history(settings.reps, settings.sets));
+        };
+
+        let top_weight = last.top_weight();
+        let increment = increment_in(settings.increment, settings.increment_unit, context.unit);
+        let rpe = last
+            .top_sets()
+            .iter()
+            .filter_map(|set| set.rpe)
+            .reduce(f64::max);
+
+        // Without a logged RPE there's nothing to regulate on
+        let Some(rpe) = rpe else {
+            return Ok(Suggestion {
+                weight: Some(round_to(top_weight, increment)),
+                reps: settings.reps,
+                sets: settings.sets,
+                reason: SuggestionReason::Repeat,
+            });
+        };
+
+        let change = top_weight * (settings.target_rpe - rpe) * settings.percent_per_rpe / 100.0;
+        let weight = round_to(top_weight + change, increment);
+        let reason = if weight > top_weight {
+            SuggestionReason::Increase
+        } else if weight < top_weight {
+            SuggestionReason::Decrease
+        } else {
+            SuggestionReason::Repeat
+        };
+
+        Ok(Suggestion {
+            weight: Some(weight),
+            reps: settings.reps,
+            sets: settings.sets,
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn session(day: u32, weight: f64, reps: &[i32], rpe: Option<f64>) -> Session {
+        Session {
+            date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            sets: reps
+                .iter()
+                .map(|reps| SessionSet {
+                    reps: *reps,
+                    weight,
+                    rpe,
+                })
+                .collect(),
+        }
+    }
+
+    fn suggest(name: &str, sessions: &[Session], settings: Option<Value>) -> Suggestion {
+        let context = ProgressionContext {
+            sessions,
+            unit: WeightUnit::Kg,
+            settings: settings.as_ref(),
+        };
+        find_strategy(name).unwrap().suggest(&context).unwrap()
+    }
+
+    fn weight_and_reason(suggestion: Suggestion) -> (Option<f64>, SuggestionReason) {
+        (suggestion.weight, suggestion.reason)
+    }
+
+    #[test]
+    fn double_progression_adds_weight_at_the_top_of_the_range() {
+        let suggestion = suggest(
+            "double_progression",
+            &[session(1, 61.0, &[12, 12, 12], None)],
+            None,
+        );
+        // 61 + 2.5 is rounded onto the 2.5 kg steps
+        assert_eq!(suggestion.weight, Some(62.5));
+        assert_eq!(suggestion.reps, 8);
+        assert_eq!(suggestion.reason, SuggestionReason::Increase);
+    }
+
+    #[test]
+    fn double_progression_adds_reps_then_deloads_on_stalls() {
+        let suggestion = suggest(
+            "double_progression",
+            &[session(1, 61.0, &[10, 9, 9], None)],
+            None,
+        );
+        assert_eq!(suggestion.weight, Some(60.0));
+        assert_eq!(suggestion.reps, 10);
+        assert_eq!(suggestion.reason, SuggestionReason::Repeat);
+
+        let sessions: Vec<Session> = (1..=4)
+            .map(|day| session(day, 100.0, &[9, 9, 9], None))
+            .collect();
+        assert_eq!(
+            weight_and_reason(suggest("double_progression", &sessions, None)),
+            (Some(90.0), SuggestionReason::Deload)
+        );
+    }
+
+    #[test]
+    fn linear_progression_rounds_every_suggestion() {
+        assert_eq!(
+            weight_and_reason(suggest(
+                "linear",
+                &[session(1, 101.0, &[5, 5, 5], None)],
+                None
+            )),
+            (Some(102.5), SuggestionReason::Increase)
+        );
+        assert_eq!(
+            weight_and_reason(suggest(
+                "linear",
+                &[session(1, 101.0, &[5, 5, 4], None)],
+                None
+            )),
+            (Some(100.0), SuggestionReason::Repeat)
+        );
+
+        let sessions: Vec<Session> = (1..=3)
+            .map(|day| session(day, 100.0, &[5, 4, 3], None))
+            .collect();
+        assert_eq!(
+            weight_and_reason(suggest("linear", &sessions, None)),
+            (Some(90.0), SuggestionReason::Deload)
+        );
+    }
+
+    #[test]
+    fn linear_progression_converts_the_increment() {
+        let settings = json!({"increment": 10.0, "increment_unit": "lb"});
+        let suggestion = suggest(
+            "linear",
+            &[session(1, 100.0, &[5, 5, 5], None)],
+            Some(settings),
+        );
+        let increment = convert_weight(10.0, WeightUnit::Lb, WeightUnit::Kg);
+        assert_eq!(
+            suggestion.weight,
+            Some(round_to(100.0 + increment, increment))
+        );
+    }
+
+    #[test]
+    fn rpe_progression_moves_the_weight_towards_the_target() {
+        // 4% per point off the target of 8, rounded onto 2.5 kg
+        assert_eq!(
+            weight_and_reason(suggest("rpe", &[session(1, 100.0, &[5], Some(9.0))], None)),
+            (Some(95.0), SuggestionReason::Decrease)
+        );
+        assert_eq!(
+            weight_and_reason(suggest("rpe", &[session(1, 100.0, &[5], Some(7.0))], None)),
+            (Some(105.0), SuggestionReason::Increase)
+        );
+        assert_eq!(
+            weight_and_reason(suggest("rpe", &[session(1, 101.0, &[5], None)], None)),
+            (Some(100.0), SuggestionReason::Repeat)
+        );
+    }
+
+    #[test]
+    fn no_history_leaves_the_weight_open() {
+        for name in strategy_names() {
+            assert_eq!(suggest(name, &[], None).weight, None);
+        }
+    }
+}
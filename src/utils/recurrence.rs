@@ -0,0 +1,276 @@
+use crate::database::sea_orm_active_enums::{TaskRecurrence, WeekDay};
+use crate::utils::streaks::Streak;
+use crate::utils::time::{to_weekday, week_start_date};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::BTreeSet;
+
+// The subset of iCalendar RRULE that habits need: FREQ, INTERVAL and BYDAY
+#[derive(Debug, Clone)]
+pub struct RecurrenceRule {
+    pub frequency: TaskRecurrence,
+    pub interval: i64,
+    // Weekly rules only, empty means once anywhere in the week
+    pub days: Vec<WeekDay>,
+    pub starts_on: NaiveDate,
+    pub week_start: WeekDay,
+}
+
+fn rrule_day(week_day: WeekDay) -> &'static str {
+    match to_weekday(week_day) {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+impl RecurrenceRule {
+    // Occurrences are identified by their first day. Without BYDAY an occurrence covers the
+    // whole period, every day or week `interval` apart, and can be completed on any day in it.
+    // With BYDAY only the listed days are occurrences.
+    pub fn occurrence_for(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if date < self.starts_on {
+            return None;
+        }
+
+        match self.frequency {
+            TaskRecurrence::Daily => {
+                let days_since_start = (date - self.starts_on).num_days();
+                Some(
+                    self.starts_on
+                        + Duration::days(days_since_start / self.interval * self.interval),
+                )
+            }
+            TaskRecurrence::Weekly => {
+                let first_week = week_start_date(self.starts_on, self.week_start);
+                let week = week_start_date(date, self.week_start);
+                let weeks_since_start = (week - first_week).num_weeks();
+                if self.days.is_empty() {
+                    let period_week = weeks_since_start / self.interval * self.interval;
+                    return Some(first_week + Duration::weeks(period_week));
+                }
+
+                let is_scheduled = weeks_since_start % self.interval == 0
+                    && self
+                        .days
+                        .iter()
+                        .any(|day| to_weekday(*day) == date.weekday());
+                is_scheduled.then_some(date)
+            }
+        }
+    }
+
+    // Every occurrence that has started by `until`, oldest first. Steps a period at a time so
+    // the cost follows the number of occurrences rather than the number of days.
+    pub fn occurrences_until(&self, until: NaiveDate) -> Vec<NaiveDate> {
+        let mut occurrences: Vec<NaiveDate> = Vec::new();
+        if until < self.starts_on {
+            return occurrences;
+        }
+
+        match self.frequency {
+            TaskRecurrence::Daily => {
+                let mut date = self.starts_on;
+                while date <= until {
+                    occurrences.push(date);
+                    date += Duration::days(self.interval);
+                }
+            }
+            TaskRecurrence::Weekly => {
+                // Days of the week as offsets from the first day of the user's week
+                let week_start = to_weekday(self.week_start).num_days_from_monday();
+                let mut offsets: Vec<u32> = self
+                    .days
+                    .iter()
+                    .map(|day| (to_weekday(*day).num_days_from_monday() + 7 - week_start) % 7)
+                    .collect();
+                offsets.sort();
+                offsets.dedup();
+
+                let mut week = week_start_date(self.starts_on, self.week_start);
+                while week <= until {
+                    if offsets.is_empty() {
+                        occurrences.push(week);
+                    }
+                    for offset in &offsets {
+                        let date = week + Duration::days(*offset as i64);
+                        if date >= self.starts_on && date <= until {
+                            occurrences.push(date);
+                        }
+                    }
+                    week += Duration::weeks(self.interval);
+                }
+            }
+        }
+        occurrences
+    }
+
+    pub fn to_rrule(&self) -> String {
+        let frequency = match self.frequency {
+            TaskRecurrence::Daily => "DAILY",
+            TaskRecurrence::Weekly => "WEEKLY",
+        };
+        let mut rrule = format!("FREQ={};INTERVAL={}", frequency, self.interval);
+        if !self.days.is_empty() {
+            let days: Vec<&str> = self.days.iter().map(|day| rrule_day(*day)).collect();
+            rrule.push_str(&format!(";BYDAY={}", days.join(",")));
+        }
+        rrule
+    }
+}
+
+// Consecutive completed occurrences. Like the training streaks, an open current occurrence
+// doesn't break the current streak, only a missed earlier one does.
+pub fn occurrence_streak(occurrences: &[NaiveDate], completed: &BTreeSet<NaiveDate>) -> Streak {
+    let mut streak = Streak::default();
+    let mut run = 0;
+
+    for (index, occurrence) in occurrences.iter().enumerate() {
+        if completed.contains(occurrence) {
+            run += 1;
+            streak.longest = streak.longest.max(run);
+        } else if index + 1 < occurrences.len() {
+            run = 0;
+        }
+    }
+
+    streak.current = run;
+    streak
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn rule(frequency: TaskRecurrence, interval: i64, days: Vec<WeekDay>) -> RecurrenceRule {
+        RecurrenceRule {
+            frequency,
+            interval,
+            days,
+            // A Wednesday
+            starts_on: date(2026, 9, 2),
+            week_start: WeekDay::Monday,
+        }
+    }
+
+    // The occurrences found by asking every day which occurrence it belongs to
+    fn walk_days(rule: &RecurrenceRule, until: NaiveDate) -> Vec<NaiveDate> {
+        let mut occurrences: Vec<NaiveDate> = Vec::new();
+        let mut date = rule.starts_on;
+        while date <= until {
+            if let Some(occurrence) = rule.occurrence_for(date) {
+                if occurrences.last() != Some(&occurrence) {
+                    occurrences.push(occurrence);
+                }
+            }
+            date += Duration::days(1);
+        }
+        occurrences
+    }
+
+    #[test]
+    fn daily_occurrences_step_by_the_interval() {
+        let rule = rule(TaskRecurrence::Daily, 3, vec![]);
+        assert_eq!(
+            rule.occurrences_until(date(2026, 9, 10)),
+            vec![date(2026, 9, 2), date(2026, 9, 5), date(2026, 9, 8)]
+        );
+        assert_eq!(
+            rule.occurrence_for(date(2026, 9, 7)),
+            Some(date(2026, 9, 5))
+        );
+        assert_eq!(rule.occurrence_for(date(2026, 9, 1)), None);
+    }
+
+    #[test]
+    fn weekly_occurrences_start_on_the_first_day_of_the_week() {
+        let rule = rule(TaskRecurrence::Weekly, 2, vec![]);
+        assert_eq!(
+            rule.occurrences_until(date(2026, 9, 28)),
+            vec![date(2026, 8, 31), date(2026, 9, 14), date(2026, 9, 28)]
+        );
+    }
+
+    #[test]
+    fn weekly_occurrences_on_days_skip_days_before_the_start() {
+        let mut rule = rule(
+            TaskRecurrence::Weekly,
+            1,
+            vec![WeekDay::Monday, WeekDay::Friday, WeekDay::Sunday],
+        );
+        rule.week_start = WeekDay::Sunday;
+        assert_eq!(
+            rule.occurrences_until(date(2026, 9, 11)),
+            vec![
+                date(2026, 9, 4),
+                date(2026, 9, 6),
+                date(2026, 9, 7),
+                date(2026, 9, 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn occurrences_match_walking_every_day() {
+        let until = date(2027, 3, 1);
+        let week_days = vec![WeekDay::Tuesday, WeekDay::Wednesday, WeekDay::Saturday];
+        for interval in 1..=4 {
+            for week_start in [WeekDay::Monday, WeekDay::Thursday, WeekDay::Sunday] {
+                for (frequency, days) in [
+                    (TaskRecurrence::Daily, vec![]),
+                    (TaskRecurrence::Weekly, vec![]),
+                    (TaskRecurrence::Weekly, week_days.clone()),
+                ] {
+                    let mut rule = rule(frequency, interval, days);
+                    rule.week_start = week_start;
+                    assert_eq!(
+                        rule.occurrences_until(until),
+                        walk_days(&rule, until),
+                        "{:?}",
+                        rule
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nothing_occurs_before_the_start() {
+        let rule = rule(TaskRecurrence::Daily, 1, vec![]);
+        assert!(rule.occurrences_until(date(2026, 9, 1)).is_empty());
+    }
+
+    #[test]
+    fn rrule_lists_the_days() {
+        let rule = rule(
+            TaskRecurrence::Weekly,
+            2,
+            vec![WeekDay::Monday, WeekDay::Thursday],
+        );
+        assert_eq!(rule.to_rrule(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH");
+    }
+
+    #[test]
+    fn open_current_occurrence_keeps_the_streak() {
+        let occurrences = [
+            date(2026, 9, 1),
+            date(2026, 9, 2),
+            date(2026, 9, 3),
+            date(2026, 9, 4),
+        ];
+        let completed = BTreeSet::from([date(2026, 9, 1), date(2026, 9, 2), date(2026, 9, 3)]);
+        let streak = occurrence_streak(&occurrences, &completed);
+        assert_eq!((streak.current, streak.longest), (3, 3));
+
+        let completed = BTreeSet::from([date(2026, 9, 1), date(2026, 9, 3)]);
+        let streak = occurrence_streak(&occurrences, &completed);
+        assert_eq!((streak.current, streak.longest), (1, 1));
+    }
+}
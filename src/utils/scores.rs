@@ -0,0 +1,206 @@
+use crate::database::sea_orm_active_enums::Sex;
+
+// Coefficients follow the OpenPowerlifting implementations. Bodyweights and
+// totals are in kg, and bodyweights are clamped to each formula's valid range.
+
+fn polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0.0, |accumulator, coefficient| {
+            accumulator * x + coefficient
+        })
+}
+
+pub fn wilks(sex: Sex, bodyweight: f64, total: f64) -> f64 {
+    let (coefficients, min, max): (&[f64], f64, f64) = match sex {
+        Sex::Male => (
+            &[
+                -216.0475144,
+                16.2606339,
+                -0.002388645,
+                -0.00113732,
+                7.01863e-06,
+                -1.291e-08,
+            ],
+            40.0,
+            201.9,
+        ),
+        Sex::Female => (
+            &[
+                594.31747775582,
+                -27.23842536447,
+                0.82112226871,
+                -0.00930733913,
+                4.731582e-05,
+                -9.054e-08,
+            ],
+            26.51,
+            154.53,
+        ),
+    };
+
+    total * 500.0 / polynomial(coefficients, bodyweight.clamp(min, max))
+}
+
+pub fn wilks2(sex: Sex, bodyweight: f64, total: f64) -> f64 {
+    let (coefficients, min, max): (&[f64], f64, f64) = match sex {
+        Sex::Male => (
+            &[
+                47.46178854,
+                8.472061379,
+                0.07369410346,
+                -0.001395833811,
+                7.07665973070743e-06,
+                -1.20804336482315e-08,
+            ],
+            40.0,
+            200.95,
+        ),
+        Sex::Female => (
+            &[
+                -125.4255398,
+                13.71219419,
+                -0.03307250631,
+                -0.001050400051,
+                9.38773881462799e-06,
+                -2.3334613884954e-08,
+            ],
+            40.0,
+            150.95,
+        ),
+    };
+
+    total * 600.0 / polynomial(coefficients, bodyweight.clamp(min, max))
+}
+
+pub fn dots(sex: Sex, bodyweight: f64, total: f64) -> f64 {
+    let (coefficients, min, max): (&[f64], f64, f64) = match sex {
+        Sex::Male => (
+            &[
+                -307.75076,
+                24.0900756,
+                -0.1918759221,
+                0.0007391293,
+                -0.000001093,
+            ],
+            40.0,
+            210.0,
+        ),
+        Sex::Female => (
+            &[
+                -57.96288,
+                13.6175032,
+                -0.1126655495,
+                0.0005158568,
+                -0.0000010706,
+            ],
+            40.0,
+            150.0,
+        ),
+    };
+
+    total * 500.0 / polynomial(coefficients, bodyweight.clamp(min, max))
+}
+
+// IPF GL points for classic (raw) full power totals
+pub fn ipf_gl(sex: Sex, bodyweight: f64, total: f64) -> f64 {
+    if bodyweight < 35.0 {
+        return 0.0;
+    }
+
+    let (a, b, c) = match sex {
+        Sex::Male => (1199.72839, 1025.18162, 0.00921),
+        Sex::Female => (610.32796, 1045.59282, 0.03048),
+    };
+
+    total * 100.0 / (a - b * (-c * bodyweight).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {} to be {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn wilks_known_values() {
+        assert_close(wilks(Sex::Male, 100.0, 1000.0), 608.589);
+        assert_close(wilks(Sex::Male, 60.0, 1000.0), 852.874);
+        assert_close(wilks(Sex::Female, 60.0, 1000.0), 1114.887);
+        assert_close(wilks(Sex::Female, 100.0, 1000.0), 832.583);
+    }
+
+    #[test]
+    fn wilks2_known_values() {
+        assert_close(wilks2(Sex::Male, 100.0, 1000.0), 729.362);
+        assert_close(wilks2(Sex::Male, 60.0, 1000.0), 996.837);
+        assert_close(wilks2(Sex::Female, 60.0, 1000.0), 1319.033);
+        assert_close(wilks2(Sex::Female, 100.0, 1000.0), 1052.453);
+    }
+
+    #[test]
+    fn dots_known_values() {
+        assert_close(dots(Sex::Male, 100.0, 1000.0), 615.516);
+        assert_close(dots(Sex::Male, 60.0, 1000.0), 844.043);
+        assert_close(dots(Sex::Female, 60.0, 1000.0), 1108.546);
+        assert_close(dots(Sex::Female, 100.0, 1000.0), 853.346);
+    }
+
+    #[test]
+    fn ipf_gl_known_values() {
+        assert_close(ipf_gl(Sex::Male, 100.0, 1000.0), 126.329);
+        assert_close(ipf_gl(Sex::Male, 60.0, 1000.0), 163.992);
+        assert_close(ipf_gl(Sex::Female, 60.0, 1000.0), 226.04);
+        assert_close(ipf_gl(Sex::Female, 100.0, 1000.0), 178.345);
+    }
+
+    #[test]
+    fn bodyweights_are_clamped_to_each_formula() {
+        for sex in [Sex::Male, Sex::Female] {
+            assert_eq!(wilks2(sex, 20.0, 500.0), wilks2(sex, 40.0, 500.0));
+            assert_eq!(dots(sex, 20.0, 500.0), dots(sex, 40.0, 500.0));
+        }
+
+        assert_eq!(wilks(Sex::Male, 20.0, 500.0), wilks(Sex::Male, 40.0, 500.0));
+        assert_eq!(
+            wilks(Sex::Male, 300.0, 500.0),
+            wilks(Sex::Male, 201.9, 500.0)
+        );
+        assert_eq!(
+            wilks(Sex::Female, 10.0, 500.0),
+            wilks(Sex::Female, 26.51, 500.0)
+        );
+        assert_eq!(
+            wilks(Sex::Female, 300.0, 500.0),
+            wilks(Sex::Female, 154.53, 500.0)
+        );
+        assert_eq!(
+            wilks2(Sex::Male, 300.0, 500.0),
+            wilks2(Sex::Male, 200.95, 500.0)
+        );
+        assert_eq!(
+            wilks2(Sex::Female, 300.0, 500.0),
+            wilks2(Sex::Female, 150.95, 500.0)
+        );
+        assert_eq!(dots(Sex::Male, 300.0, 500.0), dots(Sex::Male, 210.0, 500.0));
+        assert_eq!(
+            dots(Sex::Female, 300.0, 500.0),
+            dots(Sex::Female, 150.0, 500.0)
+        );
+    }
+
+    #[test]
+    fn ipf_gl_is_zero_below_its_range() {
+        assert_eq!(ipf_gl(Sex::Male, 34.9, 500.0), 0.0);
+        assert_eq!(ipf_gl(Sex::Female, 34.9, 500.0), 0.0);
+        assert!(ipf_gl(Sex::Female, 35.0, 500.0) > 0.0);
+    }
+}
@@ -0,0 +1,221 @@
+use serde::Serialize;
+
+// Lower is better, results are grouped by how they matched before usage is considered
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    Exact,
+    Prefix,
+    Alias,
+    WordPrefix,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SearchMatch {
+    pub kind: MatchKind,
+    pub distance: usize,
+}
+
+// Case, punctuation and repeated spaces don't count, "Farmer's walk" matches "farmers walk"
+pub fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|character| *character != '\'')
+        .map(|character| {
+            if character.is_alphanumeric() {
+                character
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let substitution = previous[j] + usize::from(left_char != *right_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[right.len()]
+}
+
+// Roughly one typo per four characters, short queries have to be typed right
+fn allowed_typos(query: &str) -> usize {
+    match query.chars().count() {
+        0..=2 => 0,
+        length => (length / 4).clamp(1, 3),
+    }
+}
+
+fn match_name(query: &str, name: &str) -> Option<SearchMatch> {
+    if name == query {
+        return Some(SearchMatch {
+            kind: MatchKind::Exact,
+            distance: 0,
+        });
+    }
+    if name.starts_with(query) {
+        return Some(SearchMatch {
+            kind: MatchKind::Prefix,
+            distance: 0,
+        });
+    }
+    if name
+        .match_indices(query)
+        .any(|(index, _)| name[..index].ends_with(' '))
+    {
+        return Some(SearchMatch {
+            kind: MatchKind::WordPrefix,
+            distance: 0,
+        });
+    }
+
+    let typos = allowed_typos(query);
+    if typos == 0 {
+        return None;
+    }
+    let distance = typo_distance(query, name, typos);
+    (distance <= typos).then_some(SearchMatch {
+        kind: MatchKind::Fuzzy,
+        distance,
+    })
+}
+
+// The closest the query comes to the start of the name or of any word in it. A typo can drop
+// or add a character, so starts up to `typos` characters shorter or longer are tried too.
+fn typo_distance(query: &str, name: &str, typos: usize) -> usize {
+    let name: Vec<char> = name.chars().collect();
+    let query_length = query.chars().count();
+    let word_starts = (0..name.len()).filter(|index| *index == 0 || name[index - 1] == ' ');
+
+    word_starts
+        .flat_map(|start| {
+            let longest = (query_length + typos).min(name.len() - start);
+            (query_length.saturating_sub(typos)..=longest).map(move |length| (start, length))
+        })
+        .map(|(start, length)| {
+            let prefix: String = name[start..start + length].iter().collect();
+            edit_distance(query, &prefix)
+        })
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+// Both the query and the names are expected to be normalized. An alias hit ranks as an alias
+// match unless it was fuzzy, then it's treated like any other typo.
+pub fn match_exercise(query: &str, name: &str, aliases: &[String]) -> Option<SearchMatch> {
+    let name_match = match_name(query, name);
+    let alias_match = aliases
+        .iter()
+        .filter_map(|alias| match_name(query, alias))
+        .min()
+        .map(|alias_match| match alias_match.kind {
+            MatchKind::Exact | MatchKind::Prefix => SearchMatch {
+                kind: MatchKind::Alias,
+                distance: 0,
+            },
+            _ => alias_match,
+        });
+
+    match (name_match, alias_match) {
+        (Some(name_match), Some(alias_match)) => Some(name_match.min(alias_match)),
+        (name_match, alias_match) => name_match.or(alias_match),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(query: &str, name: &str) -> Option<MatchKind> {
+        match_exercise(&normalize(query), &normalize(name), &[]).map(|found| found.kind)
+    }
+
+    #[test]
+    fn normalize_drops_case_and_punctuation() {
+        assert_eq!(normalize("  Farmer's   Walk (DB) "), "farmers walk db");
+        assert_eq!(normalize("T-Bar Row"), "t bar row");
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("squat", "squat"), 0);
+        assert_eq!(edit_distance("sqaut", "squat"), 2);
+        assert_eq!(edit_distance("bench", "bnch"), 1);
+        assert_eq!(edit_distance("", "row"), 3);
+    }
+
+    #[test]
+    fn typo_budget_grows_with_the_query() {
+        assert_eq!(allowed_typos("ro"), 0);
+        assert_eq!(allowed_typos("row"), 1);
+        assert_eq!(allowed_typos("curls"), 1);
+        assert_eq!(allowed_typos("deadlift"), 2);
+        assert_eq!(allowed_typos("romanian deadlift"), 3);
+    }
+
+    #[test]
+    fn short_queries_have_to_be_typed_right() {
+        assert_eq!(kind("ro", "row"), Some(MatchKind::Prefix));
+        assert_eq!(kind("rw", "row"), None);
+        assert_eq!(kind("rov", "row"), Some(MatchKind::Fuzzy));
+    }
+
+    #[test]
+    fn matches_are_ranked_by_kind() {
+        assert_eq!(
+            kind("Barbell Squat", "barbell squat"),
+            Some(MatchKind::Exact)
+        );
+        assert_eq!(kind("barbell", "Barbell Squat"), Some(MatchKind::Prefix));
+        assert_eq!(kind("squat", "Barbell Squat"), Some(MatchKind::WordPrefix));
+        assert_eq!(
+            kind("barbel squat", "Barbell Squat"),
+            Some(MatchKind::Fuzzy)
+        );
+        assert_eq!(kind("deadlift", "Barbell Squat"), None);
+    }
+
+    #[test]
+    fn typos_are_checked_against_the_start_of_each_word() {
+        assert_eq!(kind("bnch", "Bench Press"), Some(MatchKind::Fuzzy));
+        assert_eq!(kind("romanan", "Romanian Deadlift"), Some(MatchKind::Fuzzy));
+        assert_eq!(kind("sqat", "Barbell Squat"), Some(MatchKind::Fuzzy));
+        assert_eq!(
+            kind("benhc pr", "Flat Barbell Bench Press"),
+            Some(MatchKind::Fuzzy)
+        );
+        assert_eq!(kind("sqat", "Bench Press"), None);
+
+        let found = match_exercise("flat barbull", "flat barbell bench press", &[]);
+        assert_eq!(
+            found,
+            Some(SearchMatch {
+                kind: MatchKind::Fuzzy,
+                distance: 1
+            })
+        );
+    }
+
+    #[test]
+    fn alias_hits_rank_as_aliases_unless_fuzzy() {
+        let aliases = vec![String::from("rdl"), String::from("stiff leg deadlift")];
+        let found = match_exercise("rdl", "romanian deadlift", &aliases);
+        assert_eq!(found.map(|found| found.kind), Some(MatchKind::Alias));
+
+        let found = match_exercise("stif leg", "romanian deadlift", &aliases);
+        assert_eq!(found.map(|found| found.kind), Some(MatchKind::Fuzzy));
+    }
+}
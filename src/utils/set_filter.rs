@@ -0,0 +1,50 @@
+use crate::database::sea_orm_active_enums::SetType;
+use axum::http::StatusCode;
+use log::warn;
+use sea_orm::{ActiveEnum, Iterable};
+
+pub struct SetTypeFilter {
+    included: Vec<SetType>,
+}
+
+impl SetTypeFilter {
+    // Both lists are comma separated set type names, `include_types` wins when both are given.
+    // Without either, everything except `default_excluded` is kept.
+    pub fn from_query(
+        include_types: Option<&str>,
+        exclude_types: Option<&str>,
+        default_excluded: &[SetType],
+    ) -> Result<Self, StatusCode> {
+        let included = if let Some(include_types) = include_types {
+            parse_set_types(include_types)?
+        } else {
+            let excluded = match exclude_types {
+                Some(exclude_types) => parse_set_types(exclude_types)?,
+                None => default_excluded.to_vec(),
+            };
+            SetType::iter()
+                .filter(|set_type| !excluded.contains(set_type))
+                .collect()
+        };
+
+        Ok(SetTypeFilter { included })
+    }
+
+    pub fn includes(&self, set_type: SetType) -> bool {
+        self.included.contains(&set_type)
+    }
+}
+
+fn parse_set_types(set_types: &str) -> Result<Vec<SetType>, StatusCode> {
+    set_types
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            SetType::try_from_value(&name.to_string()).map_err(|_| {
+                warn!("unknown set type {}", name);
+                StatusCode::BAD_REQUEST
+            })
+        })
+        .collect()
+}
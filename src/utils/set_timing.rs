@@ -0,0 +1,60 @@
+use chrono::Duration;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+// Longer gaps are a break in the workout or a set that wasn't logged, neither rest nor a set
+const MAX_GAP_SECONDS: i64 = 30 * 60;
+
+// Time under tension as supplied, else the time between starting and finishing the set
+pub fn resolve_time_under_tension(
+    time_under_tension_seconds: Option<i32>,
+    started_at: Option<DateTimeWithTimeZone>,
+    finished_at: DateTimeWithTimeZone,
+) -> Option<i32> {
+    time_under_tension_seconds.or_else(|| {
+        let seconds = (finished_at - started_at?).num_seconds();
+        (0..=MAX_GAP_SECONDS)
+            .contains(&seconds)
+            .then_some(seconds as i32)
+    })
+}
+
+// Rest as supplied, else the gap since the previous set finished. Without a start time the
+// set's own duration is taken off the gap since sets are logged when they're finished.
+pub fn resolve_rest(
+    rest_seconds: Option<i32>,
+    previous_finished_at: Option<DateTimeWithTimeZone>,
+    started_at: Option<DateTimeWithTimeZone>,
+    finished_at: DateTimeWithTimeZone,
+    set_seconds: Option<i32>,
+) -> Option<i32> {
+    if rest_seconds.is_some() {
+        return rest_seconds;
+    }
+
+    let previous_finished_at = previous_finished_at?;
+    let seconds = match started_at {
+        Some(started_at) => (started_at - previous_finished_at).num_seconds(),
+        None => {
+            (finished_at - previous_finished_at).num_seconds() - set_seconds.unwrap_or(0) as i64
+        }
+    };
+    (0..=MAX_GAP_SECONDS)
+        .contains(&seconds)
+        .then_some(seconds as i32)
+}
+
+// The latest of the given finish times before `finished_at`
+pub fn previous_finish(
+    finish_times: &[DateTimeWithTimeZone],
+    finished_at: DateTimeWithTimeZone,
+) -> Option<DateTimeWithTimeZone> {
+    finish_times
+        .iter()
+        .filter(|finish_time| **finish_time < finished_at)
+        .max()
+        .copied()
+}
+
+pub fn max_gap() -> Duration {
+    Duration::seconds(MAX_GAP_SECONDS)
+}
@@ -0,0 +1,162 @@
+use crate::database::sea_orm_active_enums::{SetGroupType, TrackingType};
+use axum::http::StatusCode;
+use log::warn;
+use std::collections::HashMap;
+
+pub fn validate_effort(rpe: Option<f64>, rir: Option<i32>) -> Result<(), StatusCode> {
+    if let Some(rpe) = rpe {
+        if !(1.0..=10.0).contains(&rpe) {
+            warn!("invalid rpe {}", rpe);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let Some(rir) = rir {
+        if rir < 0 {
+            warn!("invalid rir {}", rir);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    Ok(())
+}
+
+// The exercise's tracking type decides which of the set's fields are required
+pub fn validate_tracking(
+    tracking_type: TrackingType,
+    reps: i32,
+    weight: f64,
+    duration_seconds: Option<i32>,
+    distance_meters: Option<f64>,
+) -> Result<(), StatusCode> {
+    let has_reps = reps > 0;
+    let has_weight = weight > 0.0;
+    let has_duration = duration_seconds.is_some_and(|duration| duration > 0);
+    let has_distance = distance_meters.is_some_and(|distance| distance > 0.0);
+
+    let is_valid = match tracking_type {
+        TrackingType::WeightReps => has_reps && weight >= 0.0,
+        TrackingType::Reps => has_reps,
+        TrackingType::Duration => has_duration,
+        TrackingType::DistanceDuration => has_distance && has_duration,
+        TrackingType::WeightDistance => has_weight && has_distance,
+    };
+
+    if !is_valid || reps < 0 || weight < 0.0 {
+        warn!("set is missing fields required by {:?}", tracking_type);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
+
+pub fn validate_heart_rate(
+    avg_heart_rate: Option<i32>,
+    max_heart_rate: Option<i32>,
+) -> Result<(), StatusCode> {
+    for heart_rate in [avg_heart_rate, max_heart_rate].into_iter().flatten() {
+        if !(20..=250).contains(&heart_rate) {
+            warn!("invalid heart rate {}", heart_rate);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    if let (Some(avg_heart_rate), Some(max_heart_rate)) = (avg_heart_rate, max_heart_rate) {
+        if avg_heart_rate > max_heart_rate {
+            warn!("average heart rate above maximum");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    Ok(())
+}
+
+// Derive the pace when the client only sent distance and duration
+pub fn resolve_pace(
+    pace_seconds_per_km: Option<f64>,
+    duration_seconds: Option<i32>,
+    distance_meters: Option<f64>,
+) -> Option<f64> {
+    match (pace_seconds_per_km, duration_seconds, distance_meters) {
+        (Some(pace), _, _) => Some(pace),
+        (None, Some(duration), Some(distance)) if distance > 0.0 => {
+            Some(duration as f64 / (distance / 1000.0))
+        }
+        _ => None,
+    }
+}
+
+pub fn validate_timing(
+    rest_seconds: Option<i32>,
+    time_under_tension_seconds: Option<i32>,
+) -> Result<(), StatusCode> {
+    if rest_seconds.is_some_and(|rest| rest < 0)
+        || time_under_tension_seconds.is_some_and(|time_under_tension| time_under_tension < 0)
+    {
+        warn!(
+            "invalid rest {:?} or time under tension {:?}",
+            rest_seconds, time_under_tension_seconds
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
+
+pub fn validate_cardio(
+    duration_seconds: Option<i32>,
+    distance_meters: Option<f64>,
+    pace_seconds_per_km: Option<f64>,
+) -> Result<(), StatusCode> {
+    if duration_seconds.is_some_and(|duration| duration < 0)
+        || distance_meters.is_some_and(|distance| distance < 0.0)
+        || pace_seconds_per_km.is_some_and(|pace| pace < 0.0)
+    {
+        warn!(
+            "invalid duration {:?}, distance {:?} or pace {:?}",
+            duration_seconds, distance_meters, pace_seconds_per_km
+        );
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
+
+// Sets sharing a group id form a superset or circuit, a group without a type is a superset
+pub fn resolve_group_type(
+    order_index: Option<i32>,
+    group_id: Option<&str>,
+    group_type: Option<SetGroupType>,
+) -> Result<Option<SetGroupType>, StatusCode> {
+    if order_index.is_some_and(|order_index| order_index < 0) {
+        warn!("invalid order index {:?}", order_index);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match group_id {
+        Some(group_id) if group_id.trim().is_empty() || group_id.len() > 64 => {
+            warn!("invalid group id {}", group_id);
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Some(_) => Ok(Some(group_type.unwrap_or(SetGroupType::Superset))),
+        None if group_type.is_some() => {
+            warn!("group type given without a group id");
+            Err(StatusCode::BAD_REQUEST)
+        }
+        None => Ok(None),
+    }
+}
+
+// Within one request a group can't be both a superset and a circuit
+pub fn validate_group_types<'a>(
+    groups: impl Iterator<Item = (&'a str, SetGroupType)>,
+) -> Result<(), StatusCode> {
+    let mut group_types: HashMap<&str, SetGroupType> = HashMap::new();
+    for (group_id, group_type) in groups {
+        if *group_types.entry(group_id).or_insert(group_type) != group_type {
+            warn!("group {} mixes supersets and circuits", group_id);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use crate::utils::time::local_date;
+use chrono::{DateTime, Duration, FixedOffset};
+use chrono_tz::Tz;
+
+// A new best has to beat the old one by this much, so rounding noise doesn't reset the clock
+const MIN_IMPROVEMENT: f64 = 0.005;
+// Training days since the best that it takes to call it a stall rather than a missed week
+const MIN_SESSIONS_SINCE_BEST: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct StrengthPoint {
+    pub date: DateTime<FixedOffset>,
+    pub one_rep_max: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stall {
+    pub best_one_rep_max: f64,
+    pub best_at: DateTime<FixedOffset>,
+    pub last_trained_at: DateTime<FixedOffset>,
+    pub weeks_stalled: i64,
+}
+
+// Points must be sorted oldest first, training days are counted in the user's timezone.
+// Exercises that haven't been trained within the stall
+// window aren't stalled, just dropped from the routine.
+pub fn detect_stall(
+    points: &[StrengthPoint],
+    now: DateTime<FixedOffset>,
+    stall_weeks: i64,
+    timezone: Tz,
+) -> Option<Stall> {
+    let last_trained_at = points.last()?.date;
+    if now - last_trained_at > Duration::weeks(stall_weeks) {
+        return None;
+    }
+
+    let mut best = points.first()?;
+    for point in points {
+        if point.one_rep_max > best.one_rep_max * (1.0 + MIN_IMPROVEMENT) {
+            best = point;
+        }
+    }
+
+    let weeks_stalled = (now - best.date).num_weeks();
+    let mut days_since_best: Vec<_> = points
+        .iter()
+        .filter(|point| point.date > best.date)
+        .map(|point| local_date(&point.date, timezone))
+        .collect();
+    days_since_best.dedup();
+
+    if weeks_stalled < stall_weeks || days_since_best.len() < MIN_SESSIONS_SINCE_BEST {
+        return None;
+    }
+
+    Some(Stall {
+        best_one_rep_max: best.one_rep_max,
+        best_at: best.date,
+        last_trained_at,
+        weeks_stalled,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(date: &str, one_rep_max: f64) -> StrengthPoint {
+        StrengthPoint {
+            date: DateTime::parse_from_rfc3339(date).unwrap(),
+            one_rep_max,
+        }
+    }
+
+    #[test]
+    fn sessions_since_the_best_are_counted_on_local_days() {
+        let points = [
+            point("2024-01-01T18:00:00Z", 100.0),
+            point("2024-01-20T23:30:00Z", 99.0),
+            point("2024-01-21T00:30:00Z", 99.0),
+        ];
+        let now = DateTime::parse_from_rfc3339("2024-02-05T12:00:00Z").unwrap();
+
+        // Two UTC days, but a single evening session in New York
+        assert!(detect_stall(&points, now, 4, Tz::UTC).is_some());
+        assert!(detect_stall(&points, now, 4, Tz::America__New_York).is_none());
+    }
+
+    #[test]
+    fn exercises_dropped_from_the_routine_are_not_stalled() {
+        let points = [
+            point("2024-01-01T18:00:00Z", 100.0),
+            point("2024-01-08T18:00:00Z", 99.0),
+            point("2024-01-15T18:00:00Z", 99.0),
+        ];
+        let now = DateTime::parse_from_rfc3339("2024-03-01T12:00:00Z").unwrap();
+        assert!(detect_stall(&points, now, 4, Tz::UTC).is_none());
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::{Duration, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Debug, Clone, Copy, Default)]
+pub struct Streak {
+    pub current: i64,
+    pub longest: i64,
+}
+
+// Periods are identified by their first day and are `period_days` long. The current streak
+// survives an empty current period, it only breaks once the previous period is missed too.
+pub fn streaks(
+    periods: &BTreeSet<NaiveDate>,
+    current_period: NaiveDate,
+    period_days: i64,
+) -> Streak {
+    let step = Duration::days(period_days);
+    let mut streak = Streak::default();
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for period in periods.iter().filter(|period| **period <= current_period) {
+        run = match previous {
+            Some(previous) if *period - previous == step => run + 1,
+            _ => 1,
+        };
+        streak.longest = streak.longest.max(run);
+        previous = Some(*period);
+    }
+
+    if let Some(last) = previous {
+        if last == current_period || last == current_period - step {
+            streak.current = run;
+        }
+    }
+
+    streak
+}
@@ -0,0 +1,8 @@
+// Epley estimate, a single is taken at face value
+pub fn estimated_one_rep_max(weight: f64, reps: i32) -> f64 {
+    if reps <= 1 {
+        return weight;
+    }
+
+    weight * (1.0 + reps as f64 / 30.0)
+}
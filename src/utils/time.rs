@@ -0,0 +1,33 @@
+use crate::database::sea_orm_active_enums::WeekDay;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use chrono_tz::Tz;
+use sea_orm::prelude::DateTimeWithTimeZone;
+
+// Unknown or legacy timezone names fall back to UTC rather than failing the request
+pub fn parse_timezone(timezone: &str) -> Tz {
+    timezone.parse().unwrap_or(Tz::UTC)
+}
+
+pub fn local_date(date: &DateTimeWithTimeZone, timezone: Tz) -> NaiveDate {
+    date.with_timezone(&timezone).date_naive()
+}
+
+pub fn to_weekday(week_day: WeekDay) -> Weekday {
+    match week_day {
+        WeekDay::Monday => Weekday::Mon,
+        WeekDay::Tuesday => Weekday::Tue,
+        WeekDay::Wednesday => Weekday::Wed,
+        WeekDay::Thursday => Weekday::Thu,
+        WeekDay::Friday => Weekday::Fri,
+        WeekDay::Saturday => Weekday::Sat,
+        WeekDay::Sunday => Weekday::Sun,
+    }
+}
+
+// First day of the week containing `date`, for weeks starting on the user's chosen day
+pub fn week_start_date(date: NaiveDate, week_start: WeekDay) -> NaiveDate {
+    let days_into_week = (date.weekday().num_days_from_monday() + 7
+        - to_weekday(week_start).num_days_from_monday())
+        % 7;
+    date - Duration::days(days_into_week as i64)
+}
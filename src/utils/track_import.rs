@@ -0,0 +1,412 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, FixedOffset};
+use log::warn;
+use roxmltree::{Document, Node};
+use serde::Serialize;
+
+const EARTH_RADIUS_METERS: f64 = 6_371_000.0;
+const SPLIT_METERS: f64 = 1000.0;
+// Climbs smaller than this are treated as GPS altitude noise
+const ELEVATION_THRESHOLD_METERS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackFormat {
+    Gpx,
+    Tcx,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackPoint {
+    pub time: DateTime<FixedOffset>,
+    pub position: Option<(f64, f64)>,
+    pub elevation: Option<f64>,
+    pub heart_rate: Option<i32>,
+    // TCX devices report a cumulative distance, which also covers indoor sessions without GPS
+    pub distance: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Split {
+    pub number: usize,
+    pub distance_meters: f64,
+    pub duration_seconds: f64,
+    pub pace_seconds_per_km: f64,
+    pub elevation_gain_meters: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackSummary {
+    pub format: TrackFormat,
+    pub started_at: DateTime<FixedOffset>,
+    pub distance_meters: f64,
+    pub duration_seconds: i32,
+    pub elevation_gain_meters: f64,
+    pub avg_heart_rate: Option<i32>,
+    pub max_heart_rate: Option<i32>,
+    pub splits: Vec<Split>,
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name)
+        .and_then(|child| child.text())
+        .map(str::trim)
+}
+
+fn descendant_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.descendants()
+        .find(|descendant| descendant.is_element() && descendant.tag_name().name() == name)
+        .and_then(|descendant| descendant.text())
+        .map(str::trim)
+}
+
+fn parse_time(time: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    time.and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+}
+
+fn parse_gpx_point(node: Node) -> Option<TrackPoint> {
+    let latitude = node.attribute("lat")?.parse().ok()?;
+    let longitude = node.attribute("lon")?.parse().ok()?;
+
+    Some(TrackPoint {
+        time: parse_time(child_text(node, "time"))?,
+        position: Some((latitude, longitude)),
+        elevation: child_text(node, "ele").and_then(|elevation| elevation.parse().ok()),
+        // Garmin's TrackPointExtension, the de facto standard for heart rate in GPX
+        heart_rate: descendant_text(node, "hr").and_then(|heart_rate| heart_rate.parse().ok()),
+        distance: None,
+    })
+}
+
+fn parse_tcx_point(node: Node) -> Option<TrackPoint> {
+    let position = child(node, "Position").and_then(|position| {
+        let latitude = child_text(position, "LatitudeDegrees")?.parse().ok()?;
+        let longitude = child_text(position, "LongitudeDegrees")?.parse().ok()?;
+        Some((latitude, longitude))
+    });
+
+    Some(TrackPoint {
+        time: parse_time(child_text(node, "Time"))?,
+        position,
+        elevation: child_text(node, "AltitudeMeters").and_then(|elevation| elevation.parse().ok()),
+        heart_rate: child(node, "HeartRateBpm")
+            .and_then(|heart_rate| child_text(heart_rate, "Value"))
+            .and_then(|heart_rate| heart_rate.parse().ok()),
+        distance: child_text(node, "DistanceMeters").and_then(|distance| distance.parse().ok()),
+    })
+}
+
+// The format is sniffed from the root element so clients don't have to declare it
+pub fn parse_track(xml: &str) -> Result<(TrackFormat, Vec<TrackPoint>), StatusCode> {
+    let document = Document::parse(xml).map_err(|err| {
+        warn!("could not parse track file: {}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let root = document.root_element();
+    let (format, point_name): (TrackFormat, &str) = match root.tag_name().name() {
+        "gpx" => (TrackFormat::Gpx, "trkpt"),
+        "TrainingCenterDatabase" => (TrackFormat::Tcx, "Trackpoint"),
+        other => {
+            warn!("unsupported track file with root element {}", other);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let mut points: Vec<TrackPoint> = root
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == point_name)
+        .filter_map(|node| match format {
+            TrackFormat::Gpx => parse_gpx_point(node),
+            TrackFormat::Tcx => parse_tcx_point(node),
+        })
+        .collect();
+    points.sort_by_key(|point| point.time);
+
+    Ok((format, points))
+}
+
+fn haversine(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (from_latitude, from_longitude) = (from.0.to_radians(), from.1.to_radians());
+    let (to_latitude, to_longitude) = (to.0.to_radians(), to.1.to_radians());
+
+    let a = ((to_latitude - from_latitude) / 2.0).sin().powi(2)
+        + from_latitude.cos()
+            * to_latitude.cos()
+            * ((to_longitude - from_longitude) / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+// Cumulative distance at every point, preferring the device's own odometer when present
+fn cumulative_distances(points: &[TrackPoint]) -> Vec<f64> {
+    let mut distances = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+
+    for (index, point) in points.iter().enumerate() {
+        if index > 0 {
+            let previous = &points[index - 1];
+            let step = match (
+                previous.distance,
+                point.distance,
+                previous.position,
+                point.position,
+            ) {
+                (Some(from), Some(to), _, _) => (to - from).max(0.0),
+                (_, _, Some(from), Some(to)) => haversine(from, to),
+                _ => 0.0,
+            };
+            total += step;
+        }
+        distances.push(total);
+    }
+
+    distances
+}
+
+struct ElevationGain {
+    reference: Option<f64>,
+    gain: f64,
+}
+
+impl ElevationGain {
+    fn new() -> Self {
+        ElevationGain {
+            reference: None,
+            gain: 0.0,
+        }
+    }
+
+    fn add(&mut self, elevation: Option<f64>) {
+        let Some(elevation) = elevation else {
+            return;
+        };
+
+        match self.reference {
+            None => self.reference = Some(elevation),
+            Some(reference) if elevation - reference >= ELEVATION_THRESHOLD_METERS => {
+                self.gain += elevation - reference;
+                self.reference = Some(elevation);
+            }
+            Some(reference) if elevation < reference => self.reference = Some(elevation),
+            _ => {}
+        }
+    }
+}
+
+pub fn summarize_track(
+    format: TrackFormat,
+    points: &[TrackPoint],
+) -> Result<TrackSummary, StatusCode> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        warn!("track file contains no timed track points");
+        return Err(StatusCode::BAD_REQUEST);
+    };
+
+    let duration_seconds = (last.time - first.time).num_seconds();
+    if duration_seconds <= 0 {
+        warn!("track file spans no time");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let distances = cumulative_distances(points);
+    let mut elevation_gain = ElevationGain::new();
+    let mut splits = Vec::new();
+    let mut split_elevation_gain = 0.0;
+    let mut split_start = (0.0, 0.0);
+
+    for (index, point) in points.iter().enumerate() {
+        let gain_before = elevation_gain.gain;
+        elevation_gain.add(point.elevation);
+        split_elevation_gain += elevation_gain.gain - gain_before;
+
+        if index == 0 {
+            continue;
+        }
+
+        let elapsed = (point.time - first.time).num_milliseconds() as f64 / 1000.0;
+        let previous_elapsed =
+            (points[index - 1].time - first.time).num_milliseconds() as f64 / 1000.0;
+        let (previous_distance, distance) = (distances[index - 1], distances[index]);
+
+        // Close every split boundary crossed between the two points, interpolating its time
+        while distance - split_start.0 >= SPLIT_METERS {
+            let boundary = split_start.0 + SPLIT_METERS;
+            let fraction = (boundary - previous_distance) / (distance - previous_distance);
+            let boundary_time = previous_elapsed + fraction * (elapsed - previous_elapsed);
+            let split_duration = boundary_time - split_start.1;
+
+            splits.push(Split {
+                number: splits.len() + 1,
+                distance_meters: SPLIT_METERS,
+                duration_seconds: split_duration,
+                pace_seconds_per_km: split_duration,
+                elevation_gain_meters: split_elevation_gain,
+            });
+
+            split_start = (boundary, boundary_time);
+            split_elevation_gain = 0.0;
+        }
+    }
+
+    let total_distance = *distances.last().unwrap_or(&0.0);
+    let remaining_distance = total_distance - split_start.0;
+    if remaining_distance > 0.0 {
+        let split_duration = duration_seconds as f64 - split_start.1;
+        splits.push(Split {
+            number: splits.len() + 1,
+            distance_meters: remaining_distance,
+            duration_seconds: split_duration,
+            pace_seconds_per_km: split_duration / (remaining_distance / 1000.0),
+            elevation_gain_meters: split_elevation_gain,
+        });
+    }
+
+    let heart_rates: Vec<i32> = points.iter().filter_map(|point| point.heart_rate).collect();
+    // Summed as i64 so very long or densely sampled tracks can't overflow
+    let avg_heart_rate = (!heart_rates.is_empty()).then(|| {
+        (heart_rates
+            .iter()
+            .map(|heart_rate| *heart_rate as i64)
+            .sum::<i64>()
+            / heart_rates.len() as i64) as i32
+    });
+
+    Ok(TrackSummary {
+        format,
+        started_at: first.time,
+        distance_meters: total_distance,
+        duration_seconds: duration_seconds as i32,
+        elevation_gain_meters: elevation_gain.gain,
+        avg_heart_rate,
+        max_heart_rate: heart_rates.iter().max().copied(),
+        splits,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUN_GPX: &str = include_str!("../../tests/fixtures/run.gpx");
+    const SINGLE_POINT_GPX: &str = include_str!("../../tests/fixtures/single_point.gpx");
+    const TREADMILL_TCX: &str = include_str!("../../tests/fixtures/treadmill_no_heart_rate.tcx");
+    const RIDE_TCX: &str = include_str!("../../tests/fixtures/ride.tcx");
+
+    // Distance between two points on the same meridian
+    fn meridian_meters(degrees: f64) -> f64 {
+        EARTH_RADIUS_METERS * degrees.to_radians()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 0.01,
+            "expected {} but got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn summarize(xml: &str) -> Result<TrackSummary, StatusCode> {
+        let (format, points) = parse_track(xml)?;
+        summarize_track(format, &points)
+    }
+
+    #[test]
+    fn gpx_run_totals() {
+        let summary = summarize(RUN_GPX).unwrap();
+
+        assert_eq!(summary.format, TrackFormat::Gpx);
+        assert_eq!(summary.started_at.to_rfc3339(), "2026-03-01T08:00:00+00:00");
+        assert_close(summary.distance_meters, meridian_meters(0.02));
+        assert_eq!(summary.duration_seconds, 720);
+        // +5, a 2 m dip, +7 and a 0.5 m wobble below the noise threshold
+        assert_close(summary.elevation_gain_meters, 12.0);
+        assert_eq!(summary.avg_heart_rate, Some(160));
+        assert_eq!(summary.max_heart_rate, Some(180));
+    }
+
+    #[test]
+    fn gpx_run_splits() {
+        let summary = summarize(RUN_GPX).unwrap();
+        // Constant speed, so every full kilometre takes the same time
+        let seconds_per_km = 720.0 / meridian_meters(0.02) * 1000.0;
+
+        assert_eq!(summary.splits.len(), 3);
+        for split in &summary.splits[..2] {
+            assert_close(split.distance_meters, 1000.0);
+            assert_close(split.duration_seconds, seconds_per_km);
+            assert_close(split.pace_seconds_per_km, seconds_per_km);
+        }
+        assert_close(summary.splits[0].elevation_gain_meters, 5.0);
+        assert_close(summary.splits[1].elevation_gain_meters, 7.0);
+
+        let last = &summary.splits[2];
+        assert_eq!(last.number, 3);
+        assert_close(last.distance_meters, meridian_meters(0.02) - 2000.0);
+        assert_close(last.duration_seconds, 720.0 - 2.0 * seconds_per_km);
+        assert_close(last.pace_seconds_per_km, seconds_per_km);
+        assert_close(last.elevation_gain_meters, 0.0);
+    }
+
+    #[test]
+    fn tcx_without_heart_rate_uses_device_distance() {
+        let summary = summarize(TREADMILL_TCX).unwrap();
+
+        assert_eq!(summary.format, TrackFormat::Tcx);
+        assert_close(summary.distance_meters, 2500.0);
+        assert_eq!(summary.duration_seconds, 600);
+        assert_close(summary.elevation_gain_meters, 0.0);
+        assert_eq!(summary.avg_heart_rate, None);
+        assert_eq!(summary.max_heart_rate, None);
+
+        let durations: Vec<f64> = summary
+            .splits
+            .iter()
+            .map(|split| split.duration_seconds)
+            .collect();
+        assert_eq!(durations.len(), 3);
+        assert_close(durations[0], 300.0);
+        assert_close(durations[1], 200.0);
+        assert_close(durations[2], 100.0);
+        assert_close(summary.splits[2].distance_meters, 500.0);
+        assert_close(summary.splits[2].pace_seconds_per_km, 200.0);
+    }
+
+    #[test]
+    fn tcx_points_are_sorted_by_time() {
+        let summary = summarize(RIDE_TCX).unwrap();
+
+        assert_eq!(summary.started_at.to_rfc3339(), "2026-03-03T07:00:00+00:00");
+        assert_close(summary.distance_meters, meridian_meters(0.01));
+        assert_eq!(summary.duration_seconds, 120);
+        assert_close(summary.elevation_gain_meters, 10.0);
+        assert_eq!(summary.avg_heart_rate, Some(125));
+        assert_eq!(summary.max_heart_rate, Some(130));
+    }
+
+    #[test]
+    fn single_point_is_rejected() {
+        let (_, points) = parse_track(SINGLE_POINT_GPX).unwrap();
+
+        assert_eq!(points.len(), 1);
+        assert_eq!(
+            summarize_track(TrackFormat::Gpx, &points).unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn unknown_files_are_rejected() {
+        assert_eq!(
+            parse_track("<kml></kml>").unwrap_err(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(parse_track("not xml").unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+}
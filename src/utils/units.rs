@@ -0,0 +1,46 @@
+use crate::database::sea_orm_active_enums::{MeasurementKind, WeightUnit};
+use crate::database::users::Model;
+use serde::Deserialize;
+
+const KG_PER_LB: f64 = 0.45359237;
+const CM_PER_INCH: f64 = 2.54;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct UnitQuery {
+    pub unit: Option<WeightUnit>,
+}
+
+// Explicitly requested unit wins, otherwise answer in the user's preference
+pub fn resolve_unit(requested: Option<WeightUnit>, user: &Model) -> WeightUnit {
+    requested.unwrap_or(user.preferred_unit)
+}
+
+pub fn convert_weight(weight: f64, from: WeightUnit, to: WeightUnit) -> f64 {
+    match (from, to) {
+        (WeightUnit::Kg, WeightUnit::Lb) => weight / KG_PER_LB,
+        (WeightUnit::Lb, WeightUnit::Kg) => weight * KG_PER_LB,
+        _ => weight,
+    }
+}
+
+// Lengths follow the weight unit's system: kg pairs with cm, lb with inches
+pub fn convert_length(length: f64, from: WeightUnit, to: WeightUnit) -> f64 {
+    match (from, to) {
+        (WeightUnit::Kg, WeightUnit::Lb) => length / CM_PER_INCH,
+        (WeightUnit::Lb, WeightUnit::Kg) => length * CM_PER_INCH,
+        _ => length,
+    }
+}
+
+pub fn convert_measurement(
+    kind: MeasurementKind,
+    value: f64,
+    from: WeightUnit,
+    to: WeightUnit,
+) -> f64 {
+    match kind {
+        MeasurementKind::Bodyweight => convert_weight(value, from, to),
+        MeasurementKind::BodyFat => value,
+        _ => convert_length(value, from, to),
+    }
+}